@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use data;
+use save::Save;
+use stats::{RunStats, Stat};
+
+/// What an achievement's stat is compared over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scope {
+    /// The current run alone.
+    Run,
+    /// Every run added up, the current one included.
+    Lifetime,
+}
+
+/// What an achievement counts, on top of the run stats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Counter {
+    Stat(Stat),
+    /// Biggest stack of rocks cleared, only within a run.
+    LargestStack,
+    /// Runs played, only over a lifetime.
+    Runs,
+}
+
+impl Counter {
+    /// The value as shown to the player.
+    pub fn format(&self, value: u64) -> String {
+        match *self {
+            Counter::Stat(stat) => stat.format(value),
+            _ => value.to_string(),
+        }
+    }
+}
+
+pub struct Achievement {
+    pub id: String,
+    pub title: String,
+    pub scope: Scope,
+    pub counter: Counter,
+    pub target: u64,
+}
+
+impl Achievement {
+    /// How far the player got, `profile` being the save as it was when the
+    /// current run started.
+    pub fn progress(&self, run: &RunStats, profile: &Save) -> u64 {
+        match (self.scope, self.counter) {
+            (Scope::Run, Counter::Stat(stat)) => run.get(stat),
+            (Scope::Run, Counter::LargestStack) => run.largest_stack(),
+            (Scope::Lifetime, Counter::Stat(stat)) => profile.total(stat.name()) + run.get(stat),
+            (Scope::Lifetime, Counter::Runs) => profile.runs() + 1,
+            _ => 0,
+        }
+    }
+
+    /// Progress kept in the save alone, between runs. The save doesn't
+    /// keep the largest stack of a run.
+    pub fn saved_progress(&self, profile: &Save) -> Option<u64> {
+        match (self.scope, self.counter) {
+            (Scope::Run, Counter::Stat(stat)) => profile.best.get(stat.name()).cloned(),
+            (Scope::Lifetime, Counter::Stat(stat)) => Some(profile.total(stat.name())),
+            (Scope::Lifetime, Counter::Runs) => Some(profile.runs()),
+            _ => None,
+        }
+    }
+}
+
+/// Loads the achievements of a `.achievements` file, the title being
+/// everything after the target:
+///
+/// ```text
+/// achievement tall_order run      largest_stack 11 Clear an 11-rock stack
+/// achievement regular    lifetime runs          10 Play 10 runs
+/// ```
+pub fn load(path: &Path) -> Result<Vec<Achievement>, String> {
+    let mut achievements: Vec<Achievement> = Vec::new();
+
+    for entry in data::load(path)? {
+        if entry.keyword != "achievement" {
+            return Err(format!("line {}: unknown keyword `{}`", entry.line, entry.keyword));
+        }
+
+        let id: String = entry.arg(0)?;
+        let scope = match entry.arg::<String>(1)?.as_str() {
+            "run" => Scope::Run,
+            "lifetime" => Scope::Lifetime,
+            other => return Err(format!("line {}: unknown scope `{}`", entry.line, other)),
+        };
+
+        let name: String = entry.arg(2)?;
+        let counter = match (scope, name.as_str()) {
+            (Scope::Run, "largest_stack") => Counter::LargestStack,
+            (Scope::Lifetime, "runs") => Counter::Runs,
+            (_, name) => {
+                match Stat::parse(name) {
+                    Some(stat) => Counter::Stat(stat),
+                    None => {
+                        return Err(format!("line {}: `{}` can't be counted over a {}",
+                                           entry.line,
+                                           name,
+                                           entry.positional()[1]))
+                    }
+                }
+            }
+        };
+
+        let target = entry.arg(3)?;
+        let title = entry.positional()[4..].join(" ");
+
+        if title.is_empty() {
+            return Err(format!("line {}: achievement `{}` has no title", entry.line, id));
+        }
+
+        if achievements.iter().any(|achievement| achievement.id == id) {
+            return Err(format!("line {}: achievement `{}` already exists", entry.line, id));
+        }
+
+        achievements.push(Achievement {
+                              id,
+                              title,
+                              scope,
+                              counter,
+                              target,
+                          });
+    }
+
+    Ok(achievements)
+}
+
+/// Notification of an achievement unlocked during a run.
+pub struct Toast {
+    pub text: String,
+    /// Ticks since it appeared.
+    pub age: f32,
+}
+
+impl Toast {
+    /// Ticks a toast stays on screen.
+    pub const LIFETIME: f32 = 180.0;
+    /// Ticks it takes to fade in and out.
+    const FADE: f32 = 20.0;
+
+    pub fn new(text: String) -> Self {
+        Self { text, age: 0.0 }
+    }
+
+    pub fn alpha(&self) -> u8 {
+        let fade = self.age.min(Toast::LIFETIME - self.age) / Toast::FADE;
+
+        (255.0 * fade.clamp(0.0, 1.0)) as u8
+    }
+}
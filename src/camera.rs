@@ -0,0 +1,117 @@
+extern crate sdl2;
+extern crate rand;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::render::{Renderer, Texture};
+use self::rand::Rng;
+
+use config;
+
+const MAX_SHAKE: f32 = 18.0;
+const TRAUMA_DECAY: f32 = 0.025;
+
+/// Maps world coordinates to the screen. With the default values the mapping
+/// is the identity, so world and window coordinates line up.
+pub struct Camera {
+    pub center_x: f32,
+    pub center_y: f32,
+    pub zoom: f32,
+    pub target_x: f32,
+    pub target_y: f32,
+    pub target_zoom: f32,
+    pub smoothing: f32,
+    pub trauma: f32,
+    shake_x: f32,
+    shake_y: f32,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        let center_x = config::WINDOW_WIDTH as f32 / 2.0;
+        let center_y = config::WINDOW_HEIGHT as f32 / 2.0;
+
+        Self {
+            center_x,
+            center_y,
+            zoom: 1.0,
+            target_x: center_x,
+            target_y: center_y,
+            target_zoom: 1.0,
+            smoothing: 0.1,
+            trauma: 0.0,
+            shake_x: 0.0,
+            shake_y: 0.0,
+        }
+    }
+
+    /// Sets the point the camera glides towards on each update.
+    pub fn follow(&mut self, x: f32, y: f32) {
+        self.target_x = x;
+        self.target_y = y;
+    }
+
+    /// Glides towards `(x, y)` while zooming in, used to focus on the player
+    /// before leaving the scene.
+    pub fn zoom_into(&mut self, x: f32, y: f32, zoom: f32) {
+        self.follow(x, y);
+        self.target_zoom = zoom;
+    }
+
+    /// Adds screen shake. Trauma is clamped to `0.0..1.0` and the shake
+    /// strength grows with its square, so small hits stay subtle.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn update<R: Rng>(&mut self, rng: &mut R) {
+        self.center_x += (self.target_x - self.center_x) * self.smoothing;
+        self.center_y += (self.target_y - self.center_y) * self.smoothing;
+        self.zoom += (self.target_zoom - self.zoom) * self.smoothing;
+
+        let shake = MAX_SHAKE * self.trauma * self.trauma;
+        self.shake_x = shake * rng.gen_range(-1.0, 1.0);
+        self.shake_y = shake * rng.gen_range(-1.0, 1.0);
+        self.trauma = (self.trauma - TRAUMA_DECAY).max(0.0);
+    }
+
+    pub fn to_screen(&self, x: f32, y: f32) -> (i32, i32) {
+        let screen_x = (x - self.center_x) * self.zoom + config::WINDOW_WIDTH as f32 / 2.0 +
+                       self.shake_x;
+        let screen_y = (y - self.center_y) * self.zoom + config::WINDOW_HEIGHT as f32 / 2.0 +
+                       self.shake_y;
+
+        (screen_x.round() as i32, screen_y.round() as i32)
+    }
+
+    pub fn transform(&self, rect: Rect) -> Rect {
+        let (x, y) = self.to_screen(rect.x() as f32, rect.y() as f32);
+        let width = (rect.width() as f32 * self.zoom).ceil() as u32;
+        let height = (rect.height() as f32 * self.zoom).ceil() as u32;
+
+        Rect::new(x, y, width.max(1), height.max(1))
+    }
+
+    pub fn copy(&self, renderer: &mut Renderer, texture: &Texture, src: Option<Rect>, dst: Rect) {
+        renderer
+            .copy(texture, src, Some(self.transform(dst)))
+            .unwrap();
+    }
+
+    pub fn copy_ex(&self,
+                   renderer: &mut Renderer,
+                   texture: &Texture,
+                   src: Option<Rect>,
+                   dst: Rect,
+                   flip_horizontal: bool,
+                   flip_vertical: bool) {
+        renderer
+            .copy_ex(texture,
+                     src,
+                     Some(self.transform(dst)),
+                     0.00,
+                     None,
+                     flip_horizontal,
+                     flip_vertical)
+            .unwrap();
+    }
+}
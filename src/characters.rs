@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use data::{self, Entry};
+use save::Save;
+
+/// A tint of a character's sprites, bought with coins.
+#[derive(Clone, Debug)]
+pub struct Skin {
+    pub name: String,
+    /// Multiplied with the sprite's colors.
+    pub color: (u8, u8, u8),
+    /// Coins it costs, free skins are owned from the start.
+    pub price: u64,
+}
+
+/// A character the player can run as.
+#[derive(Clone, Debug)]
+pub struct Character {
+    pub name: String,
+    /// Sprite sheet with the `run`, `jump_up`, `jump_down` and `slide`
+    /// animations.
+    pub sheet: String,
+    pub scale: i32,
+    /// Pixels the sprite is moved down by, so its feet touch the ground
+    /// whatever its height.
+    pub offset_y: i32,
+    /// Offsets into the drawn sprite of what collides while standing: left,
+    /// top, width and height.
+    pub hitbox: (f32, f32, f32, f32),
+    /// The same while sliding.
+    pub slide_hitbox: (f32, f32, f32, f32),
+    /// Multiplies how high jumps go.
+    pub jump: f32,
+    /// Multiplies how fast the world moves.
+    pub speed: f32,
+    /// The first one is the default.
+    pub skins: Vec<Skin>,
+}
+
+impl Character {
+    /// The runner of `player.png`, before any file is loaded.
+    pub fn new() -> Self {
+        Self {
+            name: "runner".into(),
+            sheet: "./assets/player.sheet".into(),
+            scale: 3,
+            offset_y: 0,
+            hitbox: (30.0, 12.0, 66.0, 135.0),
+            slide_hitbox: (18.0, 87.0, 96.0, 60.0),
+            jump: 1.0,
+            speed: 1.0,
+            skins: vec![Skin {
+                            name: "default".into(),
+                            color: (255, 255, 255),
+                            price: 0,
+                        }],
+        }
+    }
+
+    /// The skin of that name, the default one when unknown.
+    pub fn skin(&self, name: &str) -> &Skin {
+        self.skins
+            .iter()
+            .find(|skin| skin.name == name)
+            .unwrap_or(&self.skins[0])
+    }
+
+    /// Scales the jump velocities so jumps go `jump` times as high.
+    pub fn velocity_scale(&self) -> f32 {
+        self.jump.sqrt()
+    }
+}
+
+/// Loads the characters of a `.characters` file, every skin following the
+/// character it belongs to:
+///
+/// ```text
+/// character runner sheet=./assets/player.sheet scale=3 hitbox=30,12,66,135 slide_hitbox=18,87,96,60
+/// skin      crimson color=255,120,120 price=150
+/// ```
+///
+/// Characters start with a free `default` skin unless their first skin is
+/// named so.
+pub fn load(path: &Path) -> Result<Vec<Character>, String> {
+    let mut characters: Vec<Character> = Vec::new();
+
+    for entry in data::load(path)? {
+        match entry.keyword.as_str() {
+            "character" => {
+                let name: String = entry.arg(0)?;
+
+                if characters.iter().any(|character| character.name == name) {
+                    return Err(format!("line {}: character `{}` already exists",
+                                       entry.line,
+                                       name));
+                }
+
+                characters.push(Character {
+                                    name,
+                                    sheet: entry
+                                        .option("sheet")
+                                        .ok_or(format!("line {}: `character` needs a sheet",
+                                                       entry.line))?
+                                        .into(),
+                                    scale: entry.option_or("scale", 1)?,
+                                    offset_y: entry.option_or("offset_y", 0)?,
+                                    hitbox: parse_hitbox(&entry, "hitbox")?,
+                                    slide_hitbox: parse_hitbox(&entry, "slide_hitbox")?,
+                                    jump: entry.option_or("jump", 1.0)?,
+                                    speed: entry.option_or("speed", 1.0)?,
+                                    skins: Vec::new(),
+                                });
+            }
+            "skin" => {
+                let character = characters
+                    .last_mut()
+                    .ok_or(format!("line {}: `skin` before any character", entry.line))?;
+                let color = match entry.option("color") {
+                    Some(color) => parse_color(color)
+                        .ok_or(format!("line {}: invalid color `{}`", entry.line, color))?,
+                    None => (255, 255, 255),
+                };
+
+                character
+                    .skins
+                    .push(Skin {
+                              name: entry.arg(0)?,
+                              color,
+                              price: entry.option_or("price", 0)?,
+                          });
+            }
+            other => return Err(format!("line {}: unknown keyword `{}`", entry.line, other)),
+        }
+    }
+
+    for character in characters.iter_mut() {
+        if character.skins.first().is_none_or(|skin| skin.name != "default") {
+            character
+                .skins
+                .insert(0,
+                        Skin {
+                            name: "default".into(),
+                            color: (255, 255, 255),
+                            price: 0,
+                        });
+        }
+    }
+
+    if characters.is_empty() {
+        return Err(format!("{}: no characters", path.display()));
+    }
+
+    Ok(characters)
+}
+
+/// A hitbox option, as `left,top,width,height`.
+fn parse_hitbox(entry: &Entry, key: &str) -> Result<(f32, f32, f32, f32), String> {
+    let value = entry
+        .option(key)
+        .ok_or(format!("line {}: `character` needs a {}", entry.line, key))?;
+    let numbers = value
+        .split(',')
+        .map(|number| number.parse::<f32>().ok())
+        .collect::<Option<Vec<f32>>>();
+
+    match numbers.as_deref() {
+        Some(&[left, top, width, height]) if width > 0.0 && height > 0.0 => {
+            Ok((left, top, width, height))
+        }
+        _ => Err(format!("line {}: invalid {} `{}`", entry.line, key, value)),
+    }
+}
+
+fn parse_color(color: &str) -> Option<(u8, u8, u8)> {
+    let channels = color
+        .split(',')
+        .map(|channel| channel.parse::<u8>().ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    match channels.as_slice() {
+        [red, green, blue] => Some((*red, *green, *blue)),
+        _ => None,
+    }
+}
+
+/// Whether a profile can use a skin, free ones always can.
+pub fn owns(save: &Save, character: &Character, skin: &Skin) -> bool {
+    skin.price == 0 || save.owns(&character.name, &skin.name)
+}
+
+/// Character and skin picked in a profile, the first character and its
+/// default skin when nothing usable is.
+pub fn selected<'a>(characters: &'a [Character], save: &Save) -> (&'a Character, &'a Skin) {
+    let character = save.setting("character")
+        .and_then(|name| characters.iter().find(|character| character.name == name))
+        .unwrap_or(&characters[0]);
+    let skin = character.skin(save.setting("skin").unwrap_or("default"));
+
+    if owns(save, character, skin) {
+        (character, skin)
+    } else {
+        (character, &character.skins[0])
+    }
+}
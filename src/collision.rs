@@ -0,0 +1,256 @@
+extern crate sdl2;
+
+use self::sdl2::rect::Rect;
+
+pub const LAYER_PLAYER: u32 = 1 << 0;
+pub const LAYER_OBSTACLE: u32 = 1 << 1;
+
+/// Axis-aligned bounding box in world coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hitbox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Hitbox {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    pub fn translate(&self, x: f32, y: f32) -> Self {
+        Self::new(self.x + x, self.y + y, self.width, self.height)
+    }
+
+    pub fn intersects(&self, other: &Hitbox) -> bool {
+        self.x < other.right() && other.x < self.right() && self.y < other.bottom() &&
+        other.y < self.bottom()
+    }
+
+    /// Shared area of both boxes, empty along an axis where they only touch.
+    pub fn overlap(&self, other: &Hitbox) -> Hitbox {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+
+        Hitbox::new(x,
+                    y,
+                    (self.right().min(other.right()) - x).max(0.0),
+                    (self.bottom().min(other.bottom()) - y).max(0.0))
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x as i32,
+                  self.y as i32,
+                  self.width.max(1.0) as u32,
+                  self.height.max(1.0) as u32)
+    }
+}
+
+/// Where and when two bodies touched during the last step.
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    pub other: usize,
+    pub layer: u32,
+    /// Fraction of the step, from `0.0` to `1.0`, at which the boxes met.
+    pub time: f32,
+    /// Surface normal of the other body at the contact, pointing towards
+    /// the queried body.
+    pub normal: (f32, f32),
+    /// Overlapping area of both boxes at the moment of contact.
+    pub overlap: Hitbox,
+}
+
+/// Sweeps `moving` along `displacement` against `target` moving along its
+/// own displacement, so fast bodies can't tunnel through each other between
+/// two ticks. Returns the time of impact and the normal of `target`.
+pub fn sweep(moving: &Hitbox,
+             displacement: (f32, f32),
+             target: &Hitbox,
+             target_displacement: (f32, f32))
+             -> Option<(f32, (f32, f32))> {
+    if moving.intersects(target) {
+        let pushes = [(target.right() - moving.x, (1.0, 0.0)),
+                      (moving.right() - target.x, (-1.0, 0.0)),
+                      (target.bottom() - moving.y, (0.0, 1.0)),
+                      (moving.bottom() - target.y, (0.0, -1.0))];
+        let normal = pushes
+            .iter()
+            .fold(pushes[0], |best, push| if push.0 < best.0 { *push } else { best })
+            .1;
+
+        return Some((0.0, normal));
+    }
+
+    let velocity = (displacement.0 - target_displacement.0, displacement.1 - target_displacement.1);
+    let axis = |velocity: f32, start: f32, end: f32, target_start: f32, target_end: f32| {
+        if velocity > 0.0 {
+            ((target_start - end) / velocity, (target_end - start) / velocity)
+        } else if velocity < 0.0 {
+            ((target_end - start) / velocity, (target_start - end) / velocity)
+        } else if end > target_start && start < target_end {
+            (f32::NEG_INFINITY, f32::INFINITY)
+        } else {
+            (f32::INFINITY, f32::NEG_INFINITY)
+        }
+    };
+
+    let (entry_x, exit_x) = axis(velocity.0, moving.x, moving.right(), target.x, target.right());
+    let (entry_y, exit_y) = axis(velocity.1,
+                                 moving.y,
+                                 moving.bottom(),
+                                 target.y,
+                                 target.bottom());
+    let entry = entry_x.max(entry_y);
+    let exit = exit_x.min(exit_y);
+
+    if entry > exit || !(0.0..=1.0).contains(&entry) {
+        return None;
+    }
+
+    let normal = if entry_x > entry_y {
+        (if velocity.0 > 0.0 { -1.0 } else { 1.0 }, 0.0)
+    } else {
+        (0.0, if velocity.1 > 0.0 { -1.0 } else { 1.0 })
+    };
+
+    Some((entry, normal))
+}
+
+/// A hitbox registered for one step. `hitbox` is the position at the start
+/// of the step and `displacement` how far the body moves during it.
+#[derive(Clone, Copy, Debug)]
+pub struct Body {
+    pub id: usize,
+    pub hitbox: Hitbox,
+    pub displacement: (f32, f32),
+    pub layer: u32,
+    pub mask: u32,
+}
+
+impl Body {
+    /// Where the body ends up at the end of the step.
+    pub fn destination(&self) -> Hitbox {
+        self.hitbox
+            .translate(self.displacement.0, self.displacement.1)
+    }
+}
+
+/// Bodies of the current step. It is rebuilt every tick, bodies only collide
+/// with those whose layer is in their mask.
+pub struct CollisionWorld {
+    pub bodies: Vec<Body>,
+}
+
+impl CollisionWorld {
+    pub fn new() -> Self {
+        Self { bodies: Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+    }
+
+    pub fn add(&mut self,
+               id: usize,
+               hitbox: Hitbox,
+               displacement: (f32, f32),
+               layer: u32,
+               mask: u32) {
+        self.bodies
+            .push(Body {
+                      id,
+                      hitbox,
+                      displacement,
+                      layer,
+                      mask,
+                  });
+    }
+
+    pub fn body(&self, id: usize) -> Option<&Body> {
+        self.bodies.iter().find(|body| body.id == id)
+    }
+
+    /// Every contact of body `id` during the step, earliest first.
+    pub fn query(&self, id: usize) -> Vec<Contact> {
+        let body = match self.body(id) {
+            Some(body) => *body,
+            None => return Vec::new(),
+        };
+
+        let mut contacts = self.bodies
+            .iter()
+            .filter(|other| other.id != id && other.layer & body.mask != 0)
+            .filter_map(|other| {
+                sweep(&body.hitbox,
+                      body.displacement,
+                      &other.hitbox,
+                      other.displacement)
+                        .map(|(time, normal)| {
+                    let at = |body: &Body| {
+                        body.hitbox
+                            .translate(body.displacement.0 * time, body.displacement.1 * time)
+                    };
+                    let (mine, theirs) = (at(&body), at(other));
+
+                    Contact {
+                        other: other.id,
+                        layer: other.layer,
+                        time,
+                        normal,
+                        overlap: mine.overlap(&theirs),
+                    }
+                })
+            })
+            .collect::<Vec<Contact>>();
+
+        contacts.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        contacts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sweep, Hitbox};
+
+    #[test]
+    fn catches_a_fast_body_passing_through_in_one_step() {
+        let player = Hitbox::new(500.0, 300.0, 66.0, 135.0);
+        let rock = Hitbox::new(400.0, 380.0, 20.0, 20.0);
+
+        // Far enough to end the step past the player without ever overlapping
+        let (time, normal) = sweep(&player, (0.0, 0.0), &rock, (300.0, 0.0)).unwrap();
+
+        assert!(time > 0.0 && time < 1.0);
+        assert_eq!(normal, (1.0, 0.0));
+    }
+
+    #[test]
+    fn misses_a_body_passing_above() {
+        let player = Hitbox::new(500.0, 300.0, 66.0, 135.0);
+        let bird = Hitbox::new(400.0, 200.0, 20.0, 20.0);
+
+        assert_eq!(sweep(&player, (0.0, 0.0), &bird, (300.0, 0.0)), None);
+    }
+
+    #[test]
+    fn touching_at_the_start_is_immediate() {
+        let player = Hitbox::new(500.0, 300.0, 66.0, 135.0);
+        let rock = Hitbox::new(560.0, 380.0, 20.0, 20.0);
+
+        assert_eq!(sweep(&player, (0.0, 0.0), &rock, (-8.0, 0.0)).map(|hit| hit.0), Some(0.0));
+    }
+}
@@ -2,3 +2,4 @@ pub const WINDOW_HEIGHT: u32 = 600;
 pub const WINDOW_WIDTH: u32 = 800;
 pub const TITLE: &str = "Endless Jumper";
 pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+pub const TICK_MILLIS: u64 = 15;
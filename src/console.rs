@@ -0,0 +1,198 @@
+extern crate sdl2;
+
+use std::path::Path;
+use std::collections::VecDeque;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::pixels::Color;
+use self::sdl2::event::Event;
+use self::sdl2::keyboard::Keycode;
+use self::sdl2::render::{Renderer, BlendMode};
+use self::sdl2::ttf::Font;
+
+use data;
+use config;
+
+const MAX_OUTPUT: usize = 200;
+const LINE_HEIGHT: i32 = 16;
+
+/// A console command as listed by `help`.
+pub struct Command {
+    pub usage: &'static str,
+    pub help: &'static str,
+}
+
+/// Commands run by the engine itself, scenes add their own through
+/// `Scene::commands` and `Scene::on_command`.
+pub const ENGINE_COMMANDS: &[Command] = &[
+    Command { usage: "help", help: "lists every command" },
+    Command {
+        usage: "scene <name> [fade|crossfade|wipe|slide_left|slide_right|slide_up|slide_down]",
+        help: "switches to another scene",
+    },
+    Command {
+        usage: "timescale <factor>",
+        help: "slows down or speeds up the simulation",
+    },
+    Command { usage: "seed <number>", help: "seeds the next run" },
+    Command { usage: "registry dump", help: "prints every registry item" },
+    Command { usage: "exec <file>", help: "runs every line of a script file" },
+];
+
+/// Drop-down console toggled with the backtick key. Typed and scripted
+/// commands are queued and run by the engine between events and the tick.
+pub struct Console {
+    pub open: bool,
+    pub input: String,
+    pub output: VecDeque<String>,
+    pub history: Vec<String>,
+    history_index: usize,
+    queue: VecDeque<String>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            output: VecDeque::new(),
+            history: Vec::new(),
+            history_index: 0,
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn print<S: Into<String>>(&mut self, line: S) {
+        if self.output.len() == MAX_OUTPUT {
+            self.output.pop_front();
+        }
+
+        self.output.push_back(line.into());
+    }
+
+    pub fn queue<S: Into<String>>(&mut self, line: S) {
+        self.queue.push_back(line.into());
+    }
+
+    pub fn next_command(&mut self) -> Option<String> {
+        self.queue.pop_front()
+    }
+
+    /// Queues every line of a script, skipping blank lines and `#` comments.
+    pub fn exec(&mut self, path: &Path) -> Result<(), String> {
+        for entry in data::load(path)? {
+            let mut line = entry.keyword.clone();
+
+            for arg in &entry.args {
+                line.push(' ');
+                line.push_str(arg);
+            }
+
+            self.queue(line);
+        }
+
+        Ok(())
+    }
+
+    /// Handles input while the console is open, returns `false` for events
+    /// it didn't consume.
+    pub fn on_event(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::KeyDown { keycode: Some(Keycode::Backquote), .. } => {
+                self.open = !self.open;
+                true
+            }
+            _ if !self.open => false,
+            Event::Quit { .. } => false,
+            Event::TextInput { ref text, .. } => {
+                self.input.extend(text.chars().filter(|character| *character != '`'));
+                true
+            }
+            Event::KeyDown { keycode: Some(Keycode::Backspace), .. } => {
+                self.input.pop();
+                true
+            }
+            Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                self.open = false;
+                true
+            }
+            Event::KeyDown { keycode: Some(Keycode::Up), .. } => {
+                if self.history_index > 0 {
+                    self.history_index -= 1;
+                    self.input = self.history[self.history_index].clone();
+                }
+                true
+            }
+            Event::KeyDown { keycode: Some(Keycode::Down), .. } => {
+                if self.history_index + 1 < self.history.len() {
+                    self.history_index += 1;
+                    self.input = self.history[self.history_index].clone();
+                } else {
+                    self.history_index = self.history.len();
+                    self.input.clear();
+                }
+                true
+            }
+            Event::KeyDown { keycode: Some(Keycode::Return), .. } => {
+                let line = self.input.trim().to_string();
+                self.input.clear();
+
+                if !line.is_empty() {
+                    self.print(format!("> {}", line));
+                    self.history.push(line.clone());
+                    self.history_index = self.history.len();
+                    self.queue(line);
+                }
+                true
+            }
+            // Keep key presses away from the scene while typing, releases
+            // still reach it so held keys don't stick
+            Event::KeyDown { .. } => true,
+            _ => false,
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, font: &Font) {
+        if !self.open {
+            return;
+        }
+
+        let height = config::WINDOW_HEIGHT as i32 / 2;
+        let visible = (height / LINE_HEIGHT - 1) as usize;
+
+        renderer.set_blend_mode(BlendMode::Blend);
+        renderer.set_draw_color(Color::RGBA(20, 20, 20, 220));
+        renderer
+            .fill_rect(Rect::new(0, 0, config::WINDOW_WIDTH, height as u32))
+            .unwrap();
+        renderer.set_blend_mode(BlendMode::None);
+
+        let skip = self.output.len().saturating_sub(visible);
+        let prompt = format!("> {}_", self.input);
+        let lines = self.output
+            .iter()
+            .skip(skip)
+            .chain(::std::iter::once(&prompt));
+
+        for (index, line) in lines.enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+
+            let surface = font.render(line)
+                .blended(Color::RGBA(220, 220, 220, 255))
+                .unwrap();
+            let texture = renderer.create_texture_from_surface(&surface).unwrap();
+            let query = texture.query();
+
+            renderer
+                .copy(&texture,
+                      None,
+                      Some(Rect::new(8,
+                                     4 + index as i32 * LINE_HEIGHT,
+                                     query.width,
+                                     query.height)))
+                .unwrap();
+        }
+    }
+}
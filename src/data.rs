@@ -0,0 +1,99 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A single line of a data file: a keyword followed by whitespace separated
+/// arguments. Arguments written as `key=value` are options and can be looked
+/// up by name, everything else is positional.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub keyword: String,
+    pub args: Vec<String>,
+    pub line: usize,
+}
+
+impl Entry {
+    pub fn positional(&self) -> Vec<&str> {
+        self.args
+            .iter()
+            .filter(|arg| !arg.contains('='))
+            .map(|arg| arg.as_str())
+            .collect()
+    }
+
+    pub fn arg<T: FromStr>(&self, index: usize) -> Result<T, String> {
+        let positional = self.positional();
+        let value = positional
+            .get(index)
+            .ok_or(format!("line {}: `{}` expects at least {} arguments",
+                           self.line,
+                           self.keyword,
+                           index + 1))?;
+
+        value
+            .parse::<T>()
+            .map_err(|_| format!("line {}: invalid argument `{}`", self.line, value))
+    }
+
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .filter_map(|arg| {
+                let mut parts = arg.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(name), Some(value)) if name == key => Some(value),
+                    _ => None,
+                }
+            })
+            .next()
+    }
+
+    pub fn option_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, String> {
+        match self.option(key) {
+            Some(value) => {
+                value
+                    .parse::<T>()
+                    .map_err(|_| format!("line {}: invalid value for `{}`", self.line, key))
+            }
+            None => Ok(default),
+        }
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.args.iter().any(|arg| arg == flag)
+    }
+}
+
+/// Parses the contents of a data file. Blank lines and everything after a `#`
+/// are ignored.
+pub fn parse(source: &str) -> Vec<Entry> {
+    source
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = line.split('#').next().unwrap_or("");
+            let mut words = line.split_whitespace().map(String::from);
+
+            words
+                .next()
+                .map(|keyword| {
+                         Entry {
+                             keyword,
+                             args: words.collect(),
+                             line: index + 1,
+                         }
+                     })
+        })
+        .collect()
+}
+
+pub fn load(path: &Path) -> Result<Vec<Entry>, String> {
+    let mut source = String::new();
+
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut source))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    Ok(parse(&source))
+}
@@ -0,0 +1,167 @@
+extern crate sdl2;
+
+use std::collections::{HashMap, VecDeque};
+
+use self::sdl2::rect::{Rect, Point};
+use self::sdl2::pixels::Color;
+use self::sdl2::render::{Renderer, BlendMode};
+use self::sdl2::ttf::Font;
+
+use engine::RegistryItem;
+use config;
+
+const GRAPH_FRAMES: usize = 120;
+const LINE_HEIGHT: i32 = 16;
+
+/// What the running scene wants shown on the debug overlay. It is cleared
+/// before every tick and scenes only need to fill it while `enabled` is set.
+pub struct DebugInfo {
+    pub enabled: bool,
+    pub boxes: Vec<(Rect, Color)>,
+    pub lines: Vec<(Point, Point, Color)>,
+    pub text: Vec<String>,
+}
+
+impl DebugInfo {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            boxes: Vec::new(),
+            lines: Vec::new(),
+            text: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.boxes.clear();
+        self.lines.clear();
+        self.text.clear();
+    }
+}
+
+/// Engine side of the overlay, keeps frame timings across scenes.
+pub struct DebugOverlay {
+    pub frame_times: VecDeque<u64>,
+    pub ticks: u64,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(GRAPH_FRAMES),
+            ticks: 0,
+        }
+    }
+
+    pub fn record_frame(&mut self, millis: u64) {
+        if self.frame_times.len() == GRAPH_FRAMES {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(millis);
+        self.ticks += 1;
+    }
+
+    fn fps(&self) -> f32 {
+        let total: u64 = self.frame_times.iter().sum();
+
+        if total == 0 {
+            0.0
+        } else {
+            self.frame_times.len() as f32 * 1000.0 / total as f32
+        }
+    }
+
+    pub fn draw(&self,
+                renderer: &mut Renderer,
+                font: &Font,
+                scene_name: &str,
+                info: &DebugInfo,
+                registry: &HashMap<String, RegistryItem>) {
+        renderer.set_blend_mode(BlendMode::Blend);
+
+        for &(rect, color) in &info.boxes {
+            renderer.set_draw_color(color);
+            renderer.draw_rect(rect).unwrap();
+        }
+
+        for &(start, end, color) in &info.lines {
+            renderer.set_draw_color(color);
+            renderer.draw_line(start, end).unwrap();
+        }
+
+        let mut text = vec![format!("{:.1} fps  {} ms  tick {}",
+                                    self.fps(),
+                                    self.frame_times.back().cloned().unwrap_or(0),
+                                    self.ticks),
+                            format!("scene: {}", scene_name)];
+        text.extend(info.text.iter().cloned());
+
+        let mut keys = registry.keys().collect::<Vec<&String>>();
+        keys.sort();
+
+        for key in keys {
+            text.push(format!("registry {} = {}", key, registry[key]));
+        }
+
+        // Text panel on the right, frame time graph below it
+        let panel_width = 300;
+        let panel_x = config::WINDOW_WIDTH as i32 - panel_width - 10;
+        let graph_height = 60;
+
+        renderer.set_draw_color(Color::RGBA(0, 0, 0, 170));
+        renderer
+            .fill_rect(Rect::new(panel_x,
+                                 10,
+                                 panel_width as u32,
+                                 (text.len() as i32 * LINE_HEIGHT + graph_height + 20) as u32))
+            .unwrap();
+
+        for (index, line) in text.iter().enumerate() {
+            let surface = font.render(line)
+                .blended(Color::RGBA(255, 255, 255, 255))
+                .unwrap();
+            let texture = renderer.create_texture_from_surface(&surface).unwrap();
+            let query = texture.query();
+
+            renderer
+                .copy(&texture,
+                      None,
+                      Some(Rect::new(panel_x + 5,
+                                     15 + index as i32 * LINE_HEIGHT,
+                                     query.width.min(panel_width as u32 - 10),
+                                     query.height)))
+                .unwrap();
+        }
+
+        let graph_bottom = 15 + text.len() as i32 * LINE_HEIGHT + graph_height;
+        let bar_width = (panel_width - 10) / GRAPH_FRAMES as i32;
+
+        for (index, millis) in self.frame_times.iter().enumerate() {
+            let height = (*millis as i32 * graph_height / 50).clamp(1, graph_height);
+            let color = if *millis > config::TICK_MILLIS + 2 {
+                Color::RGBA(255, 80, 80, 255)
+            } else {
+                Color::RGBA(80, 255, 80, 255)
+            };
+
+            renderer.set_draw_color(color);
+            renderer
+                .fill_rect(Rect::new(panel_x + 5 + index as i32 * bar_width,
+                                     graph_bottom - height,
+                                     bar_width.max(1) as u32,
+                                     height as u32))
+                .unwrap();
+        }
+
+        // Target frame time marker
+        let target = graph_bottom - config::TICK_MILLIS as i32 * graph_height / 50;
+        renderer.set_draw_color(Color::RGBA(255, 255, 255, 120));
+        renderer
+            .draw_line(Point::new(panel_x + 5, target),
+                       Point::new(panel_x + panel_width - 5, target))
+            .unwrap();
+
+        renderer.set_blend_mode(BlendMode::None);
+    }
+}
@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use data;
+
+pub const DEFAULT_PRESET: &str = "normal";
+
+/// What a curve is driven by.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Driver {
+    Points,
+    /// Pixels the world travelled since the run started.
+    Distance,
+}
+
+/// How far the current run got, fed to every curve.
+pub struct Progress {
+    pub points: u32,
+    pub distance: f32,
+}
+
+/// Piecewise linear curve. Before the first and after the last point it
+/// stays flat.
+#[derive(Clone)]
+pub struct Curve {
+    pub driver: Driver,
+    pub points: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    pub fn constant(value: f32) -> Self {
+        Self {
+            driver: Driver::Points,
+            points: vec![(0.0, value)],
+        }
+    }
+
+    pub fn value(&self, progress: &Progress) -> f32 {
+        let x = match self.driver {
+            Driver::Points => progress.points as f32,
+            Driver::Distance => progress.distance,
+        };
+
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+
+        if x <= first.0 {
+            return first.1;
+        }
+
+        for pair in self.points.windows(2) {
+            let ((start_x, start_y), (end_x, end_y)) = (pair[0], pair[1]);
+
+            if x <= end_x {
+                return start_y + (end_y - start_y) * (x - start_x) / (end_x - start_x);
+            }
+        }
+
+        last.1
+    }
+
+    fn parse(entry: &data::Entry) -> Result<Self, String> {
+        let args = entry.positional();
+        let driver = match args.first() {
+            Some(&"points") => Driver::Points,
+            Some(&"distance") => Driver::Distance,
+            _ => {
+                return Err(format!("line {}: `{}` must be driven by `points` or `distance`",
+                                   entry.line,
+                                   entry.keyword))
+            }
+        };
+
+        let mut points = Vec::new();
+
+        for point in &args[1..] {
+            let mut parts = point.splitn(2, ':');
+            let parsed = match (parts.next(), parts.next()) {
+                (Some(x), Some(y)) => {
+                    match (x.parse::<f32>(), y.parse::<f32>()) {
+                        (Ok(x), Ok(y)) => Some((x, y)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+
+            match parsed {
+                Some((x, y)) if points.last().is_none_or(|last: &(f32, f32)| x > last.0) => {
+                    points.push((x, y))
+                }
+                _ => return Err(format!("line {}: invalid curve point `{}`", entry.line, point)),
+            }
+        }
+
+        if points.is_empty() {
+            return Err(format!("line {}: `{}` needs at least one point", entry.line, entry.keyword));
+        }
+
+        Ok(Self { driver, points })
+    }
+}
+
+/// A difficulty preset. Curves left out of the file keep the values the game
+/// used before difficulty existed.
+#[derive(Clone)]
+pub struct Preset {
+    pub name: String,
+    /// World speed in pixels per tick.
+    pub speed: Curve,
+    /// Distance between the start of two consecutive obstacles.
+    pub gap: Curve,
+    /// Range of rocks in a stack, picked uniformly.
+    pub stack_min: Curve,
+    pub stack_max: Curve,
+    /// Overrides the air jumps of the player's movement.
+    pub air_jumps: Option<u32>,
+    /// Hits taken before the run ends, one is the classic instant death.
+    pub lives: u32,
+}
+
+impl Preset {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            speed: Curve::constant(8.55),
+            gap: Curve::constant(880.0),
+            stack_min: Curve::constant(1.0),
+            stack_max: Curve::constant(11.0),
+            air_jumps: None,
+            lives: 1,
+        }
+    }
+
+    pub fn speed(&self, progress: &Progress) -> f32 {
+        self.speed.value(progress)
+    }
+
+    pub fn gap(&self, progress: &Progress) -> f32 {
+        self.gap.value(progress)
+    }
+
+    /// Smallest and largest stack allowed right now, both at least one rock.
+    pub fn stacks(&self, progress: &Progress) -> (u32, u32) {
+        let min = self.stack_min.value(progress).round().max(1.0) as u32;
+        let max = self.stack_max.value(progress).round().max(1.0) as u32;
+
+        (min.min(max), max)
+    }
+}
+
+/// Loads every preset of a `.presets` file, in file order:
+///
+/// ```text
+/// preset normal
+/// speed     points  0:8.55  30:10.5
+/// stack_max points  0:5     40:11
+/// air_jumps 1
+/// lives     3
+/// ```
+pub fn load(path: &Path) -> Result<Vec<Preset>, String> {
+    let mut presets: Vec<Preset> = Vec::new();
+
+    for entry in data::load(path)? {
+        if entry.keyword == "preset" {
+            presets.push(Preset::new(&entry.arg::<String>(0)?));
+            continue;
+        }
+
+        let preset = presets
+            .last_mut()
+            .ok_or(format!("line {}: `{}` outside of a preset", entry.line, entry.keyword))?;
+
+        match entry.keyword.as_str() {
+            "speed" => preset.speed = Curve::parse(&entry)?,
+            "gap" => preset.gap = Curve::parse(&entry)?,
+            "stack_min" => preset.stack_min = Curve::parse(&entry)?,
+            "stack_max" => preset.stack_max = Curve::parse(&entry)?,
+            "air_jumps" => preset.air_jumps = Some(entry.arg(0)?),
+            "lives" => preset.lives = entry.arg::<u32>(0)?.max(1),
+            other => return Err(format!("line {}: unknown keyword `{}`", entry.line, other)),
+        }
+    }
+
+    Ok(presets)
+}
+
+/// Picks a preset by name, falling back to the default one and then to the
+/// built-in values.
+pub fn find(presets: &[Preset], name: &str) -> Preset {
+    presets
+        .iter()
+        .find(|preset| preset.name == name)
+        .or_else(|| presets.iter().find(|preset| preset.name == DEFAULT_PRESET))
+        .cloned()
+        .unwrap_or_else(|| Preset::new(DEFAULT_PRESET))
+}
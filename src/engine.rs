@@ -3,12 +3,14 @@ extern crate sdl2;
 extern crate rand;
 
 use std::fmt;
+use std::path::Path;
 use std::collections::HashMap;
 use std::time::{Instant, Duration};
 use std::thread;
 
 use self::sdl2::event::Event;
-use self::sdl2::render::Renderer;
+use self::sdl2::keyboard::Keycode;
+use self::sdl2::render::{Renderer, Texture};
 use self::sdl2::image::{INIT_PNG, INIT_JPG};
 use self::sdl2::mixer::{INIT_FLAC, AUDIO_S16LSB, Chunk};
 use self::rand::ThreadRng;
@@ -16,24 +18,33 @@ use self::sdl2::ttf::{Font, Sdl2TtfContext};
 
 use helpers;
 use config;
+use transitions::{self, Transition, ActiveTransition};
+use debug::{DebugInfo, DebugOverlay};
+use console::{Console, Command, ENGINE_COMMANDS};
 
 pub enum Loop {
     Continue,
     Break,
     GoToScene(String),
+    /// Switches scenes through a transition. The outgoing scene's last frame
+    /// is captured before its `on_unload`, then the incoming scene gets its
+    /// `on_load` and one `on_tick` whose frame is captured without being
+    /// presented. The transition plays between both frames with input
+    /// blocked, after which the incoming scene runs normally.
+    TransitionTo(String, Transition),
 }
 
 pub enum RegistryItem {
     Number(usize),
+    Text(String),
 }
 
 impl fmt::Display for RegistryItem {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,
-               "{}",
-               match self {
-                   &RegistryItem::Number(ref number) => number,
-               })
+        match *self {
+            RegistryItem::Number(number) => write!(f, "{}", number),
+            RegistryItem::Text(ref text) => write!(f, "{}", text),
+        }
     }
 }
 
@@ -46,6 +57,11 @@ pub struct Context<'a> {
     pub fonts: HashMap<String, Font<'a, 'static>>,
     pub sounds: HashMap<String, Chunk>,
     pub registry: HashMap<String, RegistryItem>,
+    pub debug: DebugInfo,
+    /// Multiplies how far the simulation advances every tick.
+    pub time_scale: f32,
+    /// Seed requested from the console for the next run, if any.
+    pub seed: Option<u64>,
 }
 
 
@@ -66,7 +82,7 @@ impl<'a> Stage<'a> {
 }
 
 impl<'a> Engine {
-    pub fn run(inital_scene: String, mut stage: Stage) {
+    pub fn run(inital_scene: String, mut stage: Stage, script: Option<String>) {
         let sdl_context = sdl2::init().unwrap();
         let video_subsystem = sdl_context.video().unwrap();
         let ttf_context = sdl2::ttf::init().unwrap();
@@ -106,91 +122,270 @@ impl<'a> Engine {
             fonts: HashMap::new(),
             sounds: HashMap::new(),
             registry: HashMap::new(),
+            debug: DebugInfo::new(),
+            time_scale: 1.0,
+            seed: None,
         };
 
+        context
+            .fonts
+            .insert("debug".into(),
+                    ttf_context
+                        .load_font(Path::new("./assets/font.ttf"), 12)
+                        .unwrap());
+
+        let scene_names = stage.scenarios.keys().cloned().collect::<Vec<String>>();
+        let mut console = Console::new();
+
+        // Startup scripts run before the first scene ticks, so a run can be
+        // set up the same way every time.
+        if let Some(path) = script {
+            if let Err(error) = console.exec(Path::new(&path)) {
+                console.print(format!("exec {}: {}", path, error));
+                console.open = true;
+            }
+        }
+
         {
-            let mut scene_name = inital_scene;
-            let mut should_load = true;
-            let mut should_unload = false;
+            let mut flow = Flow {
+                scene_name: inital_scene,
+                should_load: true,
+                should_unload: false,
+                pending_transition: None,
+            };
             let mut timer = Instant::now();
+            let mut outgoing_frame: Option<(Transition, Texture)> = None;
+            let mut transition: Option<ActiveTransition> = None;
+            let mut overlay = DebugOverlay::new();
 
             'running: loop {
                 let elapsed = helpers::get_milliseconds(&timer.elapsed());
-                if elapsed < 15 {
-                    thread::sleep(Duration::from_millis(15 - elapsed));
+                if elapsed < config::TICK_MILLIS {
+                    thread::sleep(Duration::from_millis(config::TICK_MILLIS - elapsed));
                 }
 
+                overlay.record_frame(helpers::get_milliseconds(&timer.elapsed()));
                 timer = Instant::now();
-                let ref mut scene = stage.scenarios.get_mut(&scene_name).unwrap();
 
-                if should_load {
-                    should_load = false;
-                    match scene.on_load(&mut context) {
-                        Loop::Break => {
-                            should_unload = true;
+                // Neither scene is ticked while a transition plays and input is
+                // blocked, only quitting is still honored.
+                if let Some(mut active) = transition.take() {
+                    for event in event_pump.poll_iter() {
+                        if let Event::Quit { .. } = event {
                             break 'running;
                         }
-                        Loop::GoToScene(name) => {
-                            scene_name = name;
-                            should_load = true;
-                            should_unload = true;
-                        }
-                        _ => {}
                     }
-                }
 
-                for event in event_pump.poll_iter() {
-                    match scene.on_event(event, &mut context) {
-                        Loop::Break => {
-                            should_unload = true;
-                            break 'running;
-                        }
-                        Loop::GoToScene(name) => {
-                            scene_name = name;
-                            should_load = true;
-                            should_unload = true;
-                        }
-                        _ => {}
+                    active.advance(config::TICK_MILLIS);
+                    active.draw(&mut context.renderer);
+                    context.renderer.present();
+
+                    if !active.is_finished() {
+                        transition = Some(active);
                     }
+
+                    continue;
                 }
 
-                match scene.on_tick(&mut context) {
-                    Loop::Break => {
-                        should_unload = true;
+                let scene = stage.scenarios.get_mut(&flow.scene_name).unwrap();
+
+                if flow.should_load {
+                    flow.should_load = false;
+                    if !flow.follow(scene.on_load(&mut context)) {
                         break 'running;
                     }
-                    Loop::GoToScene(name) => {
-                        scene_name = name;
-                        should_load = true;
-                        should_unload = true;
-                    }
-                    _ => {}
                 }
 
+                // The incoming scene's first frame is rendered without input so
+                // it can be captured as the end of the transition.
+                if outgoing_frame.is_none() {
+                    for event in event_pump.poll_iter() {
+                        if let Event::KeyDown { keycode: Some(Keycode::F3), .. } = event {
+                            context.debug.enabled = !context.debug.enabled;
+                            continue;
+                        }
 
-                if should_unload {
-                    should_unload = false;
+                        if console.on_event(&event) {
+                            continue;
+                        }
 
-                    match scene.on_unload(&mut context) {
-                        Loop::Break => {
-                            should_unload = true;
+                        if !flow.follow(scene.on_event(event, &mut context)) {
                             break 'running;
                         }
-                        Loop::GoToScene(name) => {
-                            scene_name = name;
-                            should_load = true;
+                    }
+
+                    // Commands stop at the first one leaving the scene, the
+                    // rest run once the next scene is loaded.
+                    while !flow.should_load {
+                        let line = match console.next_command() {
+                            Some(line) => line,
+                            None => break,
+                        };
+                        let result = run_command(&line,
+                                                 scene.as_mut(),
+                                                 &scene_names,
+                                                 &mut console,
+                                                 &mut context);
+
+                        if !flow.follow(result) {
+                            break 'running;
                         }
-                        _ => {}
                     }
                 }
+
+                context.debug.clear();
+
+                if !flow.follow(scene.on_tick(&mut context)) {
+                    break 'running;
+                }
+
+                if let Some((next, from)) = outgoing_frame.take() {
+                    let to = transitions::capture(&context.renderer);
+                    transition = Some(ActiveTransition::new(next, from, to));
+                } else {
+                    if let Some(next) = flow.pending_transition.take() {
+                        outgoing_frame = Some((next, transitions::capture(&context.renderer)));
+                    }
+
+                    if context.debug.enabled {
+                        overlay.draw(&mut context.renderer,
+                                     &context.fonts["debug"],
+                                     &flow.scene_name,
+                                     &context.debug,
+                                     &context.registry);
+                    }
+
+                    console.draw(&mut context.renderer, &context.fonts["debug"]);
+                    context.renderer.present();
+                }
+
+                if flow.should_unload {
+                    flow.should_unload = false;
+
+                    if !flow.follow(scene.on_unload(&mut context)) {
+                        break 'running;
+                    }
+
+                    // The scene being unloaded already is the one left behind
+                    flow.should_unload = false;
+                }
             }
 
-            let ref mut scene = stage.scenarios.get_mut(&scene_name).unwrap();
+            let scene = stage.scenarios.get_mut(&flow.scene_name).unwrap();
             scene.on_unload(&mut context);
         }
     }
 }
 
+/// Where the run loop goes next, following what the scene callbacks return.
+struct Flow {
+    scene_name: String,
+    should_load: bool,
+    should_unload: bool,
+    pending_transition: Option<Transition>,
+}
+
+impl Flow {
+    /// Returns `false` once the engine should stop.
+    fn follow(&mut self, result: Loop) -> bool {
+        match result {
+            Loop::Break => {
+                self.should_unload = true;
+                return false;
+            }
+            Loop::GoToScene(name) => {
+                self.scene_name = name;
+                self.should_load = true;
+                self.should_unload = true;
+            }
+            Loop::TransitionTo(name, next) => {
+                self.scene_name = name;
+                self.should_load = true;
+                self.should_unload = true;
+                self.pending_transition = Some(next);
+            }
+            Loop::Continue => {}
+        }
+
+        true
+    }
+}
+
+/// Runs a single console command, falling back to the scene for anything
+/// the engine doesn't know about.
+fn run_command(line: &str,
+               scene: &mut dyn Scene,
+               scene_names: &[String],
+               console: &mut Console,
+               context: &mut Context)
+               -> Loop {
+    let args = line.split_whitespace().collect::<Vec<&str>>();
+
+    let result = match args.as_slice() {
+        [] => return Loop::Continue,
+        ["help"] => {
+            for command in ENGINE_COMMANDS.iter().chain(scene.commands().iter()) {
+                console.print(format!("{} - {}", command.usage, command.help));
+            }
+            Ok(String::new())
+        }
+        ["scene", name] | ["scene", name, _] => {
+            if !scene_names.iter().any(|scene_name| scene_name == name) {
+                Err(format!("unknown scene `{}`", name))
+            } else if let Some(transition) = args.get(2) {
+                match Transition::parse(transition) {
+                    Ok(transition) => return Loop::TransitionTo(name.to_string(), transition),
+                    Err(error) => Err(error),
+                }
+            } else {
+                return Loop::GoToScene(name.to_string());
+            }
+        }
+        ["timescale", factor] => {
+            match factor.parse::<f32>() {
+                Ok(factor) if factor > 0.0 => {
+                    context.time_scale = factor;
+                    Ok(format!("timescale set to {}", factor))
+                }
+                _ => Err(format!("invalid timescale `{}`", factor)),
+            }
+        }
+        ["seed", seed] => {
+            match seed.parse::<u64>() {
+                Ok(seed) => {
+                    context.seed = Some(seed);
+                    Ok(format!("seed set to {}, applied on the next run", seed))
+                }
+                Err(_) => Err(format!("invalid seed `{}`", seed)),
+            }
+        }
+        ["registry", "dump"] => {
+            let mut keys = context.registry.keys().collect::<Vec<&String>>();
+            keys.sort();
+
+            for key in keys {
+                console.print(format!("{} = {}", key, context.registry[key]));
+            }
+            Ok(String::new())
+        }
+        ["exec", path] => console.exec(Path::new(path)).map(|_| String::new()),
+        _ => {
+            match scene.on_command(&args, context) {
+                Some(result) => result,
+                None => Err(format!("unknown command `{}`, try `help`", args[0])),
+            }
+        }
+    };
+
+    match result {
+        Ok(ref message) if message.is_empty() => {}
+        Ok(message) => console.print(message),
+        Err(error) => console.print(format!("error: {}", error)),
+    }
+
+    Loop::Continue
+}
+
 pub trait Scene {
     fn new() -> Self where Self: Sized;
 
@@ -206,4 +401,14 @@ pub trait Scene {
     fn on_tick(&mut self, &mut Context) -> Loop {
         Loop::Continue
     }
+
+    /// Console commands the scene understands, listed by `help`.
+    fn commands(&self) -> Vec<Command> {
+        Vec::new()
+    }
+    /// Runs a console command the engine doesn't know, `None` when the
+    /// scene doesn't know it either.
+    fn on_command(&mut self, _: &[&str], _: &mut Context) -> Option<Result<String, String>> {
+        None
+    }
 }
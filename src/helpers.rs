@@ -2,7 +2,10 @@ extern crate sdl2;
 
 use std::time::Duration;
 use self::sdl2::rect::Rect;
+use self::sdl2::pixels::Color;
+use self::sdl2::render::Texture;
 use config;
+use engine::Context;
 
 pub fn rect_centered(width: i32,
                      height: i32,
@@ -24,6 +27,53 @@ pub fn point_colliding_rect(x: i32, y: i32, rect: &Rect) -> bool {
     }
 }
 
+/// Draws a line of text in the default font, centered at offsets from the
+/// middle of the window. It is rendered again on every call, so it suits
+/// text that changes.
+pub fn draw_text(text: &str, color: Color, (x, y): (i32, i32), ctx: &mut Context) {
+    let font = ctx.fonts.get_mut("default").unwrap();
+    let surface = font.render(text).blended(color).unwrap();
+    let texture = ctx.renderer
+        .create_texture_from_surface(&surface)
+        .unwrap();
+
+    ctx.renderer
+        .copy(&texture,
+              None,
+              Some(rect_centered(9 * text.len() as i32, 18, x, y)))
+        .unwrap();
+}
+
+/// Draws a button of the menus: a black frame, red under the mouse, around
+/// a white face with the label centered on it at `(width, height)`.
+pub fn draw_button(button: Rect,
+                   label: &Texture,
+                   (width, height): (u32, u32),
+                   over: bool,
+                   ctx: &mut Context) {
+    match over {
+        true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+        false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+    }
+
+    ctx.renderer.fill_rect(button).unwrap();
+    ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+    ctx.renderer
+        .fill_rect(Rect::new(button.x() + 5,
+                             button.y() + 5,
+                             button.width() - 10,
+                             button.height() - 10))
+        .unwrap();
+
+    let center = button.center();
+
+    ctx.renderer
+        .copy(label,
+              None,
+              Some(Rect::from_center(center, width, height)))
+        .unwrap();
+}
+
 pub fn get_milliseconds(duration: &Duration) -> u64 {
     let mut total_nanosecs = duration.as_secs() * 1_000_000_000;
     total_nanosecs += duration.subsec_nanos() as u64;
@@ -4,17 +4,85 @@ mod engine;
 mod scenarios;
 mod config;
 mod helpers;
+mod data;
+mod sprites;
+mod parallax;
+mod camera;
+mod particles;
+mod transitions;
+mod collision;
+mod masks;
+mod debug;
+mod console;
+mod difficulty;
+mod patterns;
+mod physics;
+mod obstacles;
+mod pickups;
+mod powerups;
+mod save;
+mod profiles;
+mod scoring;
+mod stats;
+mod achievements;
+mod characters;
+mod shop;
+
+use std::env;
+use std::process;
+use std::path::Path;
 
 use engine::{Engine, Stage};
 use scenarios::menu::Menu;
 use scenarios::game::Game;
 use scenarios::game_over::GameOver;
+use scenarios::achievements::Achievements;
+use scenarios::profiles::Profiles;
+use scenarios::characters::Characters;
+use scenarios::shop::Shop;
 
 pub fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+            .cloned()
+    };
+
+    // `--validate <file>` checks every chunk of an obstacle file can be
+    // cleared with every difficulty preset, without opening a window
+    if let Some(chunks) = value("--validate") {
+        match Game::validate_chunks(Path::new(&chunks),
+                                    Path::new("./assets/difficulty.presets"),
+                                    Path::new("./assets/player.movement"),
+                                    Path::new("./assets/game.characters")) {
+            Ok(ref failures) if failures.is_empty() => {
+                println!("{}: every chunk is clearable", chunks)
+            }
+            Ok(failures) => {
+                for failure in failures {
+                    eprintln!("{}", failure);
+                }
+                process::exit(1);
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(2);
+            }
+        }
+        return;
+    }
+
     let mut stage = Stage::new();
     stage.add_scene::<Menu>("menu".into());
     stage.add_scene::<Game>("game".into());
     stage.add_scene::<GameOver>("game_over".into());
+    stage.add_scene::<Achievements>("achievements".into());
+    stage.add_scene::<Profiles>("profiles".into());
+    stage.add_scene::<Characters>("characters".into());
+    stage.add_scene::<Shop>("shop".into());
 
-    Engine::run("menu".into(), stage);
+    // `--exec <file>` runs a console script before the first scene starts
+    Engine::run("menu".into(), stage, value("--exec"));
 }
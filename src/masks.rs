@@ -0,0 +1,144 @@
+extern crate sdl2;
+
+use std::path::Path;
+use std::collections::HashMap;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::surface::Surface;
+use self::sdl2::pixels::PixelFormatEnum;
+use self::sdl2::render::BlendMode;
+use self::sdl2::image::LoadSurface;
+
+use sprites::SpriteSheet;
+
+/// Pixels with an alpha at or below this are treated as empty.
+const ALPHA_THRESHOLD: u8 = 32;
+
+/// Opaque pixels of a single sprite frame.
+pub struct Mask {
+    pub width: u32,
+    pub height: u32,
+    bits: Vec<bool>,
+}
+
+impl Mask {
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return false;
+        }
+
+        self.bits[(y as u32 * self.width + x as u32) as usize]
+    }
+}
+
+/// Collision masks of every frame used by a sprite sheet, keyed by the frame
+/// rect so they can be looked up from an animator's current frame.
+pub struct MaskSet {
+    masks: HashMap<(i32, i32, u32, u32), Mask>,
+}
+
+impl MaskSet {
+    pub fn from_sheet(sheet: &SpriteSheet) -> Result<Self, String> {
+        let mut source = Surface::from_file(Path::new(&sheet.texture))?;
+        let (width, height) = source.size();
+
+        // Blit onto a known 32 bit format so alpha can be read directly,
+        // whatever format (paletted, color keyed...) the image was stored in.
+        let mut pixels = Surface::new(width, height, PixelFormatEnum::RGBA8888)?;
+        source.set_blend_mode(BlendMode::None)?;
+        source.blit(None, &mut pixels, None)?;
+
+        let pitch = pixels.pitch() as usize;
+        let mut masks = HashMap::new();
+
+        pixels.with_lock(|bytes| {
+            for animation in sheet.animations.values() {
+                for frame in &animation.frames {
+                    let rect = frame.rect;
+                    let key = (rect.x(), rect.y(), rect.width(), rect.height());
+
+                    if masks.contains_key(&key) {
+                        continue;
+                    }
+
+                    let mut bits = Vec::with_capacity((rect.width() * rect.height()) as usize);
+
+                    for y in rect.y()..rect.y() + rect.height() as i32 {
+                        for x in rect.x()..rect.x() + rect.width() as i32 {
+                            let offset = y as usize * pitch + x as usize * 4;
+                            let pixel = u32::from_ne_bytes([bytes[offset],
+                                                            bytes[offset + 1],
+                                                            bytes[offset + 2],
+                                                            bytes[offset + 3]]);
+
+                            bits.push((pixel & 0xff) as u8 > ALPHA_THRESHOLD);
+                        }
+                    }
+
+                    masks.insert(key,
+                                 Mask {
+                                     width: rect.width(),
+                                     height: rect.height(),
+                                     bits,
+                                 });
+                }
+            }
+        });
+
+        Ok(Self { masks })
+    }
+
+    pub fn get(&self, rect: Rect) -> Option<&Mask> {
+        self.masks
+            .get(&(rect.x(), rect.y(), rect.width(), rect.height()))
+    }
+}
+
+/// A mask placed in the world the same way `copy_ex` draws its frame.
+pub struct Placement<'a> {
+    pub mask: &'a Mask,
+    pub x: f32,
+    pub y: f32,
+    pub scale: i32,
+    pub flip_horizontal: bool,
+}
+
+impl<'a> Placement<'a> {
+    fn right(&self) -> f32 {
+        self.x + (self.mask.width as i32 * self.scale) as f32
+    }
+
+    fn bottom(&self) -> f32 {
+        self.y + (self.mask.height as i32 * self.scale) as f32
+    }
+
+    fn is_solid_at(&self, world_x: i32, world_y: i32) -> bool {
+        let local_x = (world_x - self.x.floor() as i32) / self.scale;
+        let local_y = (world_y - self.y.floor() as i32) / self.scale;
+        let local_x = if self.flip_horizontal {
+            self.mask.width as i32 - 1 - local_x
+        } else {
+            local_x
+        };
+
+        self.mask.is_solid(local_x, local_y)
+    }
+}
+
+/// Whether two placed masks share at least one opaque pixel.
+pub fn overlaps(a: &Placement, b: &Placement) -> bool {
+    let left = a.x.max(b.x).floor() as i32;
+    let top = a.y.max(b.y).floor() as i32;
+    let right = a.right().min(b.right()).ceil() as i32;
+    let bottom = a.bottom().min(b.bottom()).ceil() as i32;
+
+    for y in top..bottom {
+        for x in left..right {
+            if a.is_solid_at(x, y) && b.is_solid_at(x, y) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
@@ -0,0 +1,177 @@
+use collision::Hitbox;
+use physics::Hurdle;
+use sprites::Animator;
+
+/// Scale every obstacle sprite is drawn at.
+pub const SCALE: i32 = 2;
+/// World y where obstacles stand, the bottom of the first rock of a stack.
+pub const GROUND: i32 = 447 + 32 + 32 * SCALE;
+
+const TILE: i32 = 32 * SCALE;
+
+// Offsets into the scaled sprites: left, top, width and bottom for a rock
+// stack.
+const ROCK_HITBOX: (f32, f32, f32, f32) = (6.0, 14.0, 52.0, 6.0);
+
+/// What an obstacle is, with the parameters its spawn rule picked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Rocks(u32),
+    /// Flying with its lowest point `altitude` pixels above the ground.
+    Bird(f32),
+    /// A hole in the ground `width` pixels wide.
+    Pit(f32),
+    /// Rolls this many pixels per tick faster than the world scrolls.
+    Log(f32),
+    /// A row of spike tiles.
+    Spikes(u32),
+}
+
+impl Kind {
+    /// Sprite sheet and animation the obstacle is drawn with.
+    pub fn sprite(&self) -> (&'static str, &'static str) {
+        match *self {
+            Kind::Rocks(_) => ("rocks", "stack"),
+            Kind::Bird(_) => ("hazards", "bird"),
+            Kind::Pit(_) => ("hazards", "pit"),
+            Kind::Log(_) => ("hazards", "log"),
+            Kind::Spikes(_) => ("hazards", "spikes"),
+        }
+    }
+
+    /// What ending a run on this obstacle reads as.
+    pub fn cause(&self) -> String {
+        match *self {
+            Kind::Rocks(1) => "Tripped over a rock".into(),
+            Kind::Rocks(rocks) => format!("Crashed into a stack of {} rocks", rocks),
+            Kind::Bird(_) => "Flew into a bird".into(),
+            Kind::Pit(_) => "Fell into a pit".into(),
+            Kind::Log(_) => "Run over by a log".into(),
+            Kind::Spikes(_) => "Landed on spikes".into(),
+        }
+    }
+
+    /// How big the obstacle counts as when scoring it.
+    pub fn size(&self) -> u32 {
+        match *self {
+            Kind::Rocks(rocks) => rocks,
+            _ => 1,
+        }
+    }
+
+    /// Speed on top of the world's.
+    pub fn speed(&self) -> f32 {
+        match *self {
+            Kind::Log(speed) => speed,
+            _ => 0.0,
+        }
+    }
+
+    pub fn hitbox(&self, position_x: f32) -> Hitbox {
+        let ground = GROUND as f32;
+
+        match *self {
+            Kind::Rocks(rocks) => {
+                let top = tile_y(rocks - 1);
+                let bottom = tile_y(0) + TILE;
+
+                Hitbox::new(position_x + ROCK_HITBOX.0,
+                            top as f32 + ROCK_HITBOX.1,
+                            ROCK_HITBOX.2,
+                            (bottom - top) as f32 - ROCK_HITBOX.1 - ROCK_HITBOX.3)
+            }
+            Kind::Bird(altitude) => Hitbox::new(position_x + 8.0, ground - altitude - 28.0, 54.0, 28.0),
+            // Only reached by a player that fell through the hole
+            Kind::Pit(width) => Hitbox::new(position_x + 12.0, ground + 20.0, width - 24.0, 20.0),
+            Kind::Log(_) => Hitbox::new(position_x + 8.0, ground - 54.0, 48.0, 48.0),
+            Kind::Spikes(count) => {
+                Hitbox::new(position_x + 4.0,
+                            ground - 24.0,
+                            (TILE * count as i32) as f32 - 8.0,
+                            24.0)
+            }
+        }
+    }
+
+    /// Span of x where the ground is missing.
+    pub fn hole(&self, position_x: f32) -> Option<(f32, f32)> {
+        match *self {
+            Kind::Pit(width) => Some((position_x + 12.0, position_x + width - 12.0)),
+            _ => None,
+        }
+    }
+
+    /// Where every frame of the obstacle is drawn, relative to its position.
+    pub fn tiles(&self) -> Vec<(i32, i32)> {
+        match *self {
+            Kind::Rocks(rocks) => (0..rocks).map(|n| (0, tile_y(n))).collect(),
+            Kind::Bird(altitude) => vec![(0, GROUND - altitude as i32 - 48)],
+            Kind::Pit(width) => {
+                (0..(width as i32 + TILE - 1) / TILE)
+                    .map(|n| (n * TILE, GROUND - 6))
+                    .collect()
+            }
+            Kind::Log(_) => vec![(0, GROUND + 2 - TILE)],
+            Kind::Spikes(count) => {
+                (0..count as i32)
+                    .map(|n| (n * TILE, GROUND + 6 - TILE))
+                    .collect()
+            }
+        }
+    }
+
+    /// The obstacle as seen by the jump solver, from a player whose hitbox
+    /// stands on the ground at `player`. Pits reach up to the ground, the
+    /// solver never lets the player step over the hole.
+    pub fn hurdle(&self, position_x: f32, player: &Hitbox) -> Hurdle {
+        let hitbox = self.hitbox(position_x);
+        let low = player.bottom() - hitbox.bottom();
+        let high = match *self {
+            Kind::Pit(_) => 0.0,
+            _ => player.bottom() - hitbox.y,
+        };
+
+        Hurdle {
+            at: player.right() - hitbox.right(),
+            width: hitbox.width,
+            low,
+            high,
+            speed: self.speed(),
+        }
+    }
+}
+
+/// Vertical position of the `n`th rock of a stack, counting from the ground
+/// up. Rocks overlap so the stack reads as a single pile.
+fn tile_y(n: u32) -> i32 {
+    447 + 32 - (n as i32 * (32 - 15))
+}
+
+#[derive(Clone)]
+pub struct Obstacle {
+    pub id: usize,
+    pub kind: Kind,
+    pub position_x: f32,
+    pub pending_point: bool,
+    /// Smallest vertical gap left between it and the player while they
+    /// overlapped horizontally.
+    pub clearance: f32,
+    pub animator: Animator,
+}
+
+impl Obstacle {
+    pub fn new(id: usize, kind: Kind, position_x: f32) -> Self {
+        Self {
+            id,
+            kind,
+            position_x,
+            pending_point: true,
+            clearance: f32::INFINITY,
+            animator: Animator::new(kind.sprite().1),
+        }
+    }
+
+    pub fn hitbox(&self) -> Hitbox {
+        self.kind.hitbox(self.position_x)
+    }
+}
@@ -0,0 +1,106 @@
+extern crate sdl2;
+
+use std::path::Path;
+use std::collections::HashMap;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::render::{Renderer, Texture};
+
+use data;
+use config;
+use camera::Camera;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Anchor {
+    Top,
+    Bottom,
+}
+
+pub struct Layer {
+    pub texture: String,
+    pub scale: u32,
+    pub speed: f32,
+    pub anchor: Anchor,
+    pub offset_y: i32,
+    pub repeat: bool,
+    pub scroll: f32,
+}
+
+/// Background layers drawn back to front, each scrolling at a fraction of
+/// the world speed. Loaded from a `.layers` file:
+///
+/// ```text
+/// layer ./assets/background-forest.png scale=3 speed=0.17 anchor=bottom repeat
+/// ```
+pub struct Parallax {
+    pub layers: Vec<Layer>,
+}
+
+impl Parallax {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut layers = Vec::new();
+
+        for entry in data::load(path)? {
+            if entry.keyword != "layer" {
+                return Err(format!("line {}: unknown keyword `{}`", entry.line, entry.keyword));
+            }
+
+            let anchor = match entry.option("anchor").unwrap_or("bottom") {
+                "top" => Anchor::Top,
+                "bottom" => Anchor::Bottom,
+                other => return Err(format!("line {}: unknown anchor `{}`", entry.line, other)),
+            };
+
+            layers.push(Layer {
+                            texture: entry.arg(0)?,
+                            scale: entry.option_or("scale", 1)?,
+                            speed: entry.option_or("speed", 1.0)?,
+                            anchor,
+                            offset_y: entry.option_or("offset", 0)?,
+                            repeat: entry.has_flag("repeat"),
+                            scroll: 0.0,
+                        });
+        }
+
+        Ok(Self { layers })
+    }
+
+    /// Moves every layer by its share of the distance the world travelled.
+    pub fn update(&mut self, world_distance: f32) {
+        for layer in self.layers.iter_mut() {
+            layer.scroll += world_distance * layer.speed;
+        }
+    }
+
+    pub fn draw(&self,
+                renderer: &mut Renderer,
+                camera: &Camera,
+                textures: &HashMap<String, Texture>) {
+        for layer in &self.layers {
+            let texture = textures.get(&layer.texture).unwrap();
+            let image_width = (texture.query().width * layer.scale) as i32;
+            let image_height = (texture.query().height * layer.scale) as i32;
+            let y = match layer.anchor {
+                Anchor::Top => layer.offset_y,
+                Anchor::Bottom => config::WINDOW_HEIGHT as i32 - image_height + layer.offset_y,
+            };
+
+            // Tiles start one image to the left so the wrap-around is never
+            // visible while the layer scrolls to the right.
+            let (start_at, tiles) = if layer.repeat {
+                let x = layer.scroll as i32 % image_width;
+                (x - image_width, config::WINDOW_WIDTH as i32 / image_width + 2)
+            } else {
+                (layer.scroll as i32, 1)
+            };
+
+            for n in 0..tiles {
+                let destination = Rect::new(start_at + image_width * n,
+                                            y,
+                                            image_width as u32,
+                                            image_height as u32);
+                camera.copy(renderer, texture, None, destination);
+            }
+        }
+    }
+}
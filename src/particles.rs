@@ -0,0 +1,360 @@
+extern crate sdl2;
+extern crate rand;
+
+use std::path::Path;
+use std::collections::HashMap;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::pixels::Color;
+use self::sdl2::render::{Renderer, Texture, BlendMode};
+use self::sdl2::gfx::primitives::DrawRenderer;
+use self::rand::Rng;
+
+use data;
+use camera::Camera;
+
+const POOL_SIZE: usize = 512;
+
+#[derive(Clone, Copy)]
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Range {
+    fn parse(value: &str) -> Result<Self, String> {
+        let mut parts = value.splitn(2, "..");
+        let min = parts.next().unwrap_or("").parse::<f32>();
+        let max = parts.next().map(|max| max.parse::<f32>());
+
+        match (min, max) {
+            (Ok(min), None) => Ok(Range { min, max: min }),
+            (Ok(min), Some(Ok(max))) => Ok(Range { min, max }),
+            _ => Err(format!("invalid range `{}`", value)),
+        }
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> f32 {
+        if self.min < self.max {
+            rng.gen_range(self.min, self.max)
+        } else {
+            self.min
+        }
+    }
+
+    pub fn lerp(&self, progress: f32) -> f32 {
+        self.min + (self.max - self.min) * progress
+    }
+}
+
+#[derive(Clone)]
+pub enum Shape {
+    Square,
+    Circle,
+    Texture(String, Rect),
+}
+
+/// Parameters of one effect, loaded from a `.particles` file:
+///
+/// ```text
+/// effect landing burst=20 lifetime=20..40 velocity_x=-3..3 velocity_y=-3..0
+///        gravity=0.15 size=6 color=120,100,80 color_end=200,190,170 alpha=255..0
+/// ```
+///
+/// `lifetime` is in ticks, `rate` spawns particles per tick for as long as
+/// the emitter lives and `scroll` is the fraction of the world speed that
+/// carries the particles along with the ground.
+#[derive(Clone)]
+pub struct EffectConfig {
+    pub burst: u32,
+    pub rate: f32,
+    pub duration: u32,
+    pub lifetime: Range,
+    pub velocity_x: Range,
+    pub velocity_y: Range,
+    pub spread_x: Range,
+    pub spread_y: Range,
+    pub gravity: f32,
+    pub scroll: f32,
+    pub size: Range,
+    pub color: (u8, u8, u8),
+    pub color_end: (u8, u8, u8),
+    pub alpha: Range,
+    pub shape: Shape,
+}
+
+#[derive(Clone)]
+struct Particle {
+    alive: bool,
+    x: f32,
+    y: f32,
+    velocity_x: f32,
+    velocity_y: f32,
+    age: f32,
+    lifetime: f32,
+    size: f32,
+    effect: usize,
+}
+
+struct Emitter {
+    effect: usize,
+    x: f32,
+    y: f32,
+    /// Ticks left spawning.
+    remaining: f32,
+    accumulator: f32,
+}
+
+pub struct ParticleSystem {
+    effects: Vec<EffectConfig>,
+    names: HashMap<String, usize>,
+    particles: Vec<Particle>,
+    emitters: Vec<Emitter>,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self {
+            effects: Vec::new(),
+            names: HashMap::new(),
+            particles: vec![Particle {
+                                alive: false,
+                                x: 0.0,
+                                y: 0.0,
+                                velocity_x: 0.0,
+                                velocity_y: 0.0,
+                                age: 0.0,
+                                lifetime: 0.0,
+                                size: 0.0,
+                                effect: 0,
+                            };
+                            POOL_SIZE],
+            emitters: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut system = Self::new();
+
+        for entry in data::load(path)? {
+            if entry.keyword != "effect" {
+                return Err(format!("line {}: unknown keyword `{}`", entry.line, entry.keyword));
+            }
+
+            let range = |key: &str, default: &str| {
+                Range::parse(entry.option(key).unwrap_or(default))
+                    .map_err(|err| format!("line {}: {}", entry.line, err))
+            };
+            let color = |key: &str, default: &str| {
+                let value = entry.option(key).unwrap_or(default);
+                let channels = value
+                    .split(',')
+                    .map(|channel| channel.parse::<u8>())
+                    .collect::<Result<Vec<u8>, _>>();
+
+                match channels {
+                    Ok(ref channels) if channels.len() == 3 => {
+                        Ok((channels[0], channels[1], channels[2]))
+                    }
+                    _ => Err(format!("line {}: invalid color `{}`", entry.line, value)),
+                }
+            };
+
+            let shape = match entry.option("shape").unwrap_or("square") {
+                "square" => Shape::Square,
+                "circle" => Shape::Circle,
+                "texture" => {
+                    let source = entry
+                        .option("source")
+                        .unwrap_or("")
+                        .split(',')
+                        .map(|number| number.parse::<i32>())
+                        .collect::<Result<Vec<i32>, _>>();
+
+                    match (entry.option("texture"), source) {
+                        (Some(texture), Ok(ref source)) if source.len() == 4 => {
+                            Shape::Texture(texture.into(),
+                                           Rect::new(source[0],
+                                                     source[1],
+                                                     source[2] as u32,
+                                                     source[3] as u32))
+                        }
+                        _ => {
+                            return Err(format!("line {}: textured particles need `texture` and \
+                                                `source=x,y,w,h`",
+                                               entry.line))
+                        }
+                    }
+                }
+                other => return Err(format!("line {}: unknown shape `{}`", entry.line, other)),
+            };
+
+            let effect = EffectConfig {
+                burst: entry.option_or("burst", 0)?,
+                rate: entry.option_or("rate", 0.0)?,
+                duration: entry.option_or("duration", 0)?,
+                lifetime: range("lifetime", "30")?,
+                velocity_x: range("velocity_x", "0")?,
+                velocity_y: range("velocity_y", "0")?,
+                spread_x: range("spread_x", "0")?,
+                spread_y: range("spread_y", "0")?,
+                gravity: entry.option_or("gravity", 0.0)?,
+                scroll: entry.option_or("scroll", 0.0)?,
+                size: range("size", "4")?,
+                color: color("color", "255,255,255")?,
+                color_end: color("color_end", entry.option("color").unwrap_or("255,255,255"))?,
+                alpha: range("alpha", "255")?,
+                shape,
+            };
+
+            system.names.insert(entry.arg(0)?, system.effects.len());
+            system.effects.push(effect);
+        }
+
+        Ok(system)
+    }
+
+    pub fn textures(&self) -> Vec<String> {
+        self.effects
+            .iter()
+            .filter_map(|effect| match effect.shape {
+                            Shape::Texture(ref texture, _) => Some(texture.clone()),
+                            _ => None,
+                        })
+            .collect()
+    }
+
+    /// Starts an effect at a world position: its burst is spawned right away
+    /// and, if it has a spawn rate, an emitter keeps spawning for `duration`
+    /// ticks.
+    pub fn emit<R: Rng>(&mut self, name: &str, x: f32, y: f32, rng: &mut R) {
+        let effect = match self.names.get(name) {
+            Some(effect) => *effect,
+            None => return,
+        };
+
+        for _ in 0..self.effects[effect].burst {
+            self.spawn(effect, x, y, rng);
+        }
+
+        if self.effects[effect].rate > 0.0 {
+            self.emitters
+                .push(Emitter {
+                          effect,
+                          x,
+                          y,
+                          remaining: self.effects[effect].duration as f32,
+                          accumulator: 0.0,
+                      });
+        }
+    }
+
+    fn spawn<R: Rng>(&mut self, effect: usize, x: f32, y: f32, rng: &mut R) {
+        let config = &self.effects[effect];
+
+        // When the pool is exhausted new particles are simply dropped
+        if let Some(particle) = self.particles.iter_mut().find(|particle| !particle.alive) {
+            particle.alive = true;
+            particle.x = x + config.spread_x.sample(rng);
+            particle.y = y + config.spread_y.sample(rng);
+            particle.velocity_x = config.velocity_x.sample(rng);
+            particle.velocity_y = config.velocity_y.sample(rng);
+            particle.age = 0.0;
+            particle.lifetime = config.lifetime.sample(rng).max(1.0);
+            particle.size = config.size.sample(rng);
+            particle.effect = effect;
+        }
+    }
+
+    /// Advances every particle and emitter by `step` of a tick, while the
+    /// world moved `world_delta` pixels.
+    pub fn update<R: Rng>(&mut self, world_delta: f32, step: f32, rng: &mut R) {
+        let mut spawns = Vec::new();
+
+        for emitter in self.emitters.iter_mut() {
+            let config = &self.effects[emitter.effect];
+
+            emitter.x += world_delta * config.scroll;
+            emitter.accumulator += config.rate * step;
+
+            while emitter.accumulator >= 1.0 {
+                emitter.accumulator -= 1.0;
+                spawns.push((emitter.effect, emitter.x, emitter.y));
+            }
+
+            emitter.remaining -= step;
+        }
+
+        self.emitters.retain(|emitter| emitter.remaining > 0.0);
+
+        for (effect, x, y) in spawns {
+            self.spawn(effect, x, y, rng);
+        }
+
+        for particle in self.particles.iter_mut().filter(|particle| particle.alive) {
+            let config = &self.effects[particle.effect];
+
+            particle.velocity_y += config.gravity * step;
+            particle.x += particle.velocity_x * step + world_delta * config.scroll;
+            particle.y += particle.velocity_y * step;
+            particle.age += step;
+
+            if particle.age >= particle.lifetime {
+                particle.alive = false;
+            }
+        }
+    }
+
+    pub fn draw(&self,
+                renderer: &mut Renderer,
+                camera: &Camera,
+                textures: &mut HashMap<String, Texture>) {
+        renderer.set_blend_mode(BlendMode::Blend);
+
+        for particle in self.particles.iter().filter(|particle| particle.alive) {
+            let config = &self.effects[particle.effect];
+            let progress = particle.age / particle.lifetime;
+            let channel = |start: u8, end: u8| {
+                (start as f32 + (end as f32 - start as f32) * progress) as u8
+            };
+            let color = (channel(config.color.0, config.color_end.0),
+                         channel(config.color.1, config.color_end.1),
+                         channel(config.color.2, config.color_end.2));
+            let alpha = config.alpha.lerp(progress).clamp(0.0, 255.0) as u8;
+            let size = particle.size.max(1.0);
+            let destination = Rect::new((particle.x - size / 2.0) as i32,
+                                        (particle.y - size / 2.0) as i32,
+                                        size as u32,
+                                        size as u32);
+
+            match config.shape {
+                Shape::Square => {
+                    renderer.set_draw_color(Color::RGBA(color.0, color.1, color.2, alpha));
+                    renderer.fill_rect(camera.transform(destination)).unwrap();
+                }
+                Shape::Circle => {
+                    let (x, y) = camera.to_screen(particle.x, particle.y);
+                    let radius = (size * camera.zoom / 2.0).max(1.0) as i16;
+
+                    renderer
+                        .filled_circle(x as i16,
+                                       y as i16,
+                                       radius,
+                                       Color::RGBA(color.0, color.1, color.2, alpha))
+                        .unwrap();
+                }
+                Shape::Texture(ref name, source) => {
+                    if let Some(texture) = textures.get_mut(name) {
+                        texture.set_color_mod(color.0, color.1, color.2);
+                        texture.set_alpha_mod(alpha);
+                        camera.copy(renderer, texture, Some(source), destination);
+                        texture.set_color_mod(255, 255, 255);
+                        texture.set_alpha_mod(255);
+                    }
+                }
+            }
+        }
+
+        renderer.set_blend_mode(BlendMode::None);
+    }
+}
@@ -0,0 +1,332 @@
+extern crate rand;
+
+use std::path::Path;
+
+use self::rand::Rng;
+
+use data;
+use obstacles::Kind;
+use pickups::{self, Collectible, Spawn};
+use powerups::Power;
+
+/// Attempts at rolling a clearable chunk before falling back to a single
+/// stack of the smallest height.
+const MAX_ATTEMPTS: usize = 8;
+/// Distance left by the empty pattern rolled when even the fallback stack
+/// can't be cleared.
+const GAP_AFTER: f32 = 200.0;
+
+/// Height of a stack in a chunk, resolved against the difficulty's current
+/// stack range when the chunk is rolled.
+#[derive(Clone, Copy)]
+pub enum Height {
+    /// A fixed number of rocks, clamped into the range.
+    Fixed(u32),
+    Low,
+    High,
+    Any,
+}
+
+/// An obstacle of a chunk before it is rolled. Only rock heights depend on
+/// the difficulty, everything else spawns as written.
+#[derive(Clone, Copy)]
+pub enum Template {
+    Rocks(Height),
+    Fixed(Kind),
+}
+
+#[derive(Clone, Copy)]
+pub struct Item {
+    /// Distance from the start of the chunk.
+    pub offset: f32,
+    pub template: Template,
+}
+
+/// A hand-authored group of obstacles spawned together.
+pub struct Chunk {
+    pub name: String,
+    pub weight: u32,
+    /// Points needed before the chunk can be picked.
+    pub min_points: u32,
+    /// Extra distance left after the chunk, on top of the difficulty's gap.
+    pub after: f32,
+    pub items: Vec<Item>,
+    pub pickups: Vec<Spawn>,
+}
+
+impl Chunk {
+    /// Distance from the start of the chunk and kind of every obstacle, with
+    /// rock heights within `(min, max)`, in spawn order. `any` picks the
+    /// height of `Any` stacks.
+    pub fn resolve<F>(&self, (min, max): (u32, u32), mut any: F) -> Vec<(f32, Kind)>
+        where F: FnMut(u32, u32) -> u32
+    {
+        let mut obstacles = self.items
+            .iter()
+            .map(|item| {
+                let kind = match item.template {
+                    Template::Rocks(Height::Fixed(rocks)) => Kind::Rocks(rocks.max(min).min(max)),
+                    Template::Rocks(Height::Low) => Kind::Rocks(min),
+                    Template::Rocks(Height::High) => Kind::Rocks(max),
+                    Template::Rocks(Height::Any) => Kind::Rocks(any(min, max)),
+                    Template::Fixed(kind) => kind,
+                };
+
+                (item.offset, kind)
+            })
+            .collect::<Vec<(f32, Kind)>>();
+
+        obstacles.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        obstacles
+    }
+}
+
+/// Obstacles of a rolled chunk, ready to spawn.
+pub struct Pattern {
+    pub name: String,
+    /// Distance from the start of the chunk and kind of every obstacle.
+    pub obstacles: Vec<(f32, Kind)>,
+    pub pickups: Vec<Spawn>,
+    pub after: f32,
+}
+
+/// Weighted set of chunks loaded from a `.chunks` file:
+///
+/// ```text
+/// chunk double weight=3 min_points=5
+/// stack  0   any
+/// stack  52  high
+/// bird   400 high
+/// arc    160 7  width=480 gem
+/// power  700 shield
+/// ```
+///
+/// Stack heights are a number of rocks, `low`, `high` or `any`. Birds fly at
+/// an altitude in pixels, `low`, `head` or `high`, pits take a width, logs
+/// the speed they roll at and spikes a number of tiles. Coins come in an
+/// `arc` or a `row` of a given count, see `pickups::arc` and `pickups::row`.
+/// A `power` is picked up on the running line unless given a `height=`.
+pub struct Patterns {
+    pub chunks: Vec<Chunk>,
+}
+
+impl Patterns {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut chunks: Vec<Chunk> = Vec::new();
+
+        for entry in data::load(path)? {
+            match entry.keyword.as_str() {
+                "chunk" => {
+                    chunks.push(Chunk {
+                                    name: entry.arg(0)?,
+                                    weight: entry.option_or("weight", 1)?,
+                                    min_points: entry.option_or("min_points", 0)?,
+                                    after: entry.option_or("after", 0.0)?,
+                                    items: Vec::new(),
+                                    pickups: Vec::new(),
+                                });
+                }
+                "arc" | "row" => {
+                    let (offset, count) = (entry.arg(0)?, entry.arg(1)?);
+                    let gem = entry.has_flag("gem");
+                    let spawns = if entry.keyword == "arc" {
+                        pickups::arc(offset,
+                                     count,
+                                     entry.option_or("width", 480.0)?,
+                                     entry.option_or("height", 200.0)?,
+                                     gem)
+                    } else {
+                        pickups::row(offset, count, entry.option_or("spacing", 48.0)?, gem)
+                    };
+
+                    chunks
+                        .last_mut()
+                        .ok_or(format!("line {}: `{}` outside of a chunk", entry.line, entry.keyword))?
+                        .pickups
+                        .extend(spawns);
+                }
+                "power" => {
+                    let name: String = entry.arg(1)?;
+                    let power = Power::parse(&name)
+                        .ok_or(format!("line {}: unknown power `{}`", entry.line, name))?;
+                    let spawn = Spawn {
+                        offset: entry.arg(0)?,
+                        height: entry.option_or("height", pickups::RUN_HEIGHT)?,
+                        kind: Collectible::Power(power),
+                    };
+
+                    chunks
+                        .last_mut()
+                        .ok_or(format!("line {}: `power` outside of a chunk", entry.line))?
+                        .pickups
+                        .push(spawn);
+                }
+                keyword => {
+                    let template = match keyword {
+                        "stack" => {
+                            let height = match entry.arg::<String>(1)?.as_str() {
+                                "low" => Height::Low,
+                                "high" => Height::High,
+                                "any" => Height::Any,
+                                _ => Height::Fixed(entry.arg(1)?),
+                            };
+
+                            Template::Rocks(height)
+                        }
+                        "bird" => {
+                            let altitude = match entry.arg::<String>(1)?.as_str() {
+                                "low" => 10.0,
+                                "head" => 70.0,
+                                "high" => 150.0,
+                                _ => entry.arg(1)?,
+                            };
+
+                            Template::Fixed(Kind::Bird(altitude))
+                        }
+                        "pit" => Template::Fixed(Kind::Pit(entry.arg(1)?)),
+                        "log" => Template::Fixed(Kind::Log(entry.arg(1)?)),
+                        "spikes" => Template::Fixed(Kind::Spikes(entry.arg(1)?)),
+                        other => {
+                            return Err(format!("line {}: unknown keyword `{}`", entry.line, other));
+                        }
+                    };
+                    let item = Item {
+                        offset: entry.arg(0)?,
+                        template,
+                    };
+
+                    chunks
+                        .last_mut()
+                        .ok_or(format!("line {}: `{}` outside of a chunk", entry.line, keyword))?
+                        .items
+                        .push(item);
+                }
+            }
+        }
+
+        if let Some(chunk) = chunks.iter().find(|chunk| chunk.items.is_empty()) {
+            return Err(format!("chunk `{}` has no obstacles", chunk.name));
+        }
+
+        // Pickups spawn in order, the same as obstacles
+        for chunk in chunks.iter_mut() {
+            chunk
+                .pickups
+                .sort_by(|a, b| a.offset.partial_cmp(&b.offset).unwrap());
+        }
+
+        Ok(Self { chunks })
+    }
+
+    /// Rolls a chunk among those unlocked at `points`, picked by weight, with
+    /// stacks within `(min, max)` rocks. Chunks `clearable` rejects are
+    /// rolled again, then a single stack of the smallest height is tried,
+    /// and when even that can't be cleared the pattern is an empty gap.
+    pub fn generate<R, F>(&self,
+                          rng: &mut R,
+                          points: u32,
+                          range: (u32, u32),
+                          clearable: F)
+                          -> Pattern
+        where R: Rng,
+              F: Fn(&[(f32, Kind)]) -> bool
+    {
+        let available = self.chunks
+            .iter()
+            .filter(|chunk| chunk.min_points <= points && chunk.weight > 0)
+            .collect::<Vec<&Chunk>>();
+        let total = available.iter().map(|chunk| chunk.weight).sum::<u32>();
+
+        for _ in 0..if total > 0 { MAX_ATTEMPTS } else { 0 } {
+            let mut roll = rng.gen_range(0, total);
+            let chunk = available
+                .iter()
+                .find(|chunk| if roll < chunk.weight {
+                          true
+                      } else {
+                          roll -= chunk.weight;
+                          false
+                      })
+                .unwrap();
+
+            let obstacles = chunk.resolve(range, |min, max| rng.gen_range(min, max + 1));
+
+            if clearable(&obstacles) {
+                return Pattern {
+                           name: chunk.name.clone(),
+                           obstacles,
+                           pickups: chunk.pickups.clone(),
+                           after: chunk.after,
+                       };
+            }
+        }
+
+        let fallback = vec![(0.0, Kind::Rocks(range.0))];
+
+        if clearable(&fallback) {
+            return Pattern {
+                       name: "fallback".into(),
+                       obstacles: fallback,
+                       pickups: Vec::new(),
+                       after: 0.0,
+                   };
+        }
+
+        Pattern {
+            name: "gap".into(),
+            obstacles: Vec::new(),
+            pickups: Vec::new(),
+            after: GAP_AFTER,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rand::{SeedableRng, XorShiftRng};
+
+    use obstacles::Kind;
+    use super::{Chunk, Height, Item, Patterns, Template, GAP_AFTER};
+
+    /// A single chunk of two stacks of `rocks`.
+    fn patterns(rocks: u32) -> Patterns {
+        let stack = |offset| {
+            Item {
+                offset,
+                template: Template::Rocks(Height::Fixed(rocks)),
+            }
+        };
+
+        Patterns {
+            chunks: vec![Chunk {
+                             name: "double".into(),
+                             weight: 1,
+                             min_points: 0,
+                             after: 0.0,
+                             items: vec![stack(0.0), stack(52.0)],
+                             pickups: Vec::new(),
+                         }],
+        }
+    }
+
+    fn rng() -> XorShiftRng {
+        XorShiftRng::from_seed([1, 2, 3, 4])
+    }
+
+    #[test]
+    fn falls_back_to_a_single_low_stack() {
+        let pattern = patterns(3).generate(&mut rng(), 0, (1, 3), |obstacles| obstacles.len() < 2);
+
+        assert_eq!(pattern.name, "fallback");
+        assert_eq!(pattern.obstacles, vec![(0.0, Kind::Rocks(1))]);
+    }
+
+    #[test]
+    fn leaves_a_gap_when_the_fallback_cant_be_cleared() {
+        let pattern = patterns(3).generate(&mut rng(), 0, (1, 3), |_| false);
+
+        assert_eq!(pattern.name, "gap");
+        assert!(pattern.obstacles.is_empty());
+        assert_eq!(pattern.after, GAP_AFTER);
+    }
+}
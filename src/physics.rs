@@ -0,0 +1,356 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use data;
+
+/// Vertical movement of the player, shared by the game and the jump solver
+/// so both agree on what a jump can clear.
+#[derive(Clone, Copy)]
+pub struct JumpPhysics {
+    pub gravity: f32,
+    /// Upward speed given when the jump starts.
+    pub jump_velocity: f32,
+    /// Upward speed the jump is cut to when the key is released early.
+    pub release_velocity: f32,
+    pub ground_y: f32,
+}
+
+impl JumpPhysics {
+    /// Moves `position_y` by one tick scaled by `step`. Returns the speed
+    /// the player hit the ground at when it touches it again.
+    pub fn step(&self, position_y: &mut f32, velocity_y: &mut f32, step: f32) -> Option<f32> {
+        *velocity_y += self.gravity * step;
+        *position_y += *velocity_y * step;
+
+        if *position_y > self.ground_y {
+            let impact = *velocity_y;
+
+            *position_y = self.ground_y;
+            *velocity_y = 0.0;
+            Some(impact)
+        } else {
+            None
+        }
+    }
+
+    /// Vertical velocity after releasing the jump key.
+    pub fn release(&self, velocity_y: f32) -> f32 {
+        velocity_y.max(-self.release_velocity)
+    }
+
+    /// Ticks until a held jump can't go any higher.
+    fn longest_hold(&self) -> u32 {
+        ((self.jump_velocity - self.release_velocity) / self.gravity).ceil().max(0.0) as u32
+    }
+
+    /// Ticks spent in the air by a jump held for the whole way up.
+    fn airtime(&self) -> u32 {
+        (2.0 * self.jump_velocity / self.gravity).ceil() as u32 + 1
+    }
+}
+
+/// How the player jumps, loaded from a `.movement` file:
+///
+/// ```text
+/// gravity           0.5
+/// jump_velocity     16
+/// release_velocity  6
+/// coyote            6
+/// buffer            8
+/// air_jumps         0
+/// air_jump_velocity 12
+/// ```
+///
+/// Velocities are in pixels per tick and everything else in ticks.
+#[derive(Clone, Copy)]
+pub struct Movement {
+    pub jump: JumpPhysics,
+    /// Ticks after leaving the ground without jumping a jump is still
+    /// allowed.
+    pub coyote: u32,
+    /// Ticks a jump pressed in the air is kept for, it happens on landing.
+    pub buffer: u32,
+    /// Jumps allowed before landing again.
+    pub air_jumps: u32,
+    pub air_jump_velocity: f32,
+}
+
+impl Movement {
+    pub fn new(ground_y: f32) -> Self {
+        Self {
+            jump: JumpPhysics {
+                gravity: 0.5,
+                jump_velocity: 16.0,
+                release_velocity: 6.0,
+                ground_y,
+            },
+            coyote: 0,
+            buffer: 0,
+            air_jumps: 0,
+            air_jump_velocity: 12.0,
+        }
+    }
+
+    /// Values left out of the file keep the ones of `Movement::new`.
+    pub fn load(path: &Path, ground_y: f32) -> Result<Self, String> {
+        let mut movement = Movement::new(ground_y);
+
+        for entry in data::load(path)? {
+            match entry.keyword.as_str() {
+                "gravity" => movement.jump.gravity = entry.arg(0)?,
+                "jump_velocity" => movement.jump.jump_velocity = entry.arg(0)?,
+                "release_velocity" => movement.jump.release_velocity = entry.arg(0)?,
+                "coyote" => movement.coyote = entry.arg(0)?,
+                "buffer" => movement.buffer = entry.arg(0)?,
+                "air_jumps" => movement.air_jumps = entry.arg(0)?,
+                "air_jump_velocity" => movement.air_jump_velocity = entry.arg(0)?,
+                other => return Err(format!("line {}: unknown keyword `{}`", entry.line, other)),
+            }
+        }
+
+        Ok(movement)
+    }
+}
+
+/// Obstacle seen from the player standing on the ground: `at` is how far
+/// the world travels before its leading edge reaches the player's hitbox.
+#[derive(Clone, Copy, Debug)]
+pub struct Hurdle {
+    pub at: f32,
+    pub width: f32,
+    /// Band of heights above the player's feet it occupies, negative below
+    /// the ground.
+    pub low: f32,
+    pub high: f32,
+    /// How much faster than the world it moves.
+    pub speed: f32,
+}
+
+/// The player's hitbox in one pose, measured from the standing hitbox the
+/// hurdles are placed against.
+#[derive(Clone, Copy)]
+pub struct Stance {
+    /// How far its right edge is behind the standing one's.
+    pub offset: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// A slide on the ground: `ticks` spent in `stance`, then `cooldown` ticks
+/// before the next one.
+#[derive(Clone, Copy)]
+pub struct Slide {
+    pub ticks: u32,
+    pub cooldown: u32,
+    pub stance: Stance,
+}
+
+/// A move found by `solve`, in ticks from the start of the layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Move {
+    Jump {
+        tick: u32,
+        /// Ticks the key is held before being released.
+        hold: u32,
+    },
+    Slide { tick: u32 },
+}
+
+/// A layout to check: hurdles coming at the player while the world moves
+/// `speed` pixels per tick.
+pub struct Layout<'a> {
+    pub hurdles: &'a [Hurdle],
+    pub speed: f32,
+    pub standing: Stance,
+    /// Left out when the player can't slide.
+    pub slide: Option<Slide>,
+    /// Extra room required around every hurdle, in pixels.
+    pub tolerance: f32,
+}
+
+impl<'a> Layout<'a> {
+    /// Whether the player touches a hurdle `time` ticks after the hurdles
+    /// were at `at`, in `stance` with its feet `height` pixels above the
+    /// ground.
+    fn collides(&self, time: f32, height: f32, stance: &Stance) -> bool {
+        self.hurdles
+            .iter()
+            .any(|hurdle| {
+                let left = hurdle.at - time * (self.speed + hurdle.speed) - self.tolerance -
+                           stance.offset;
+                let right = left + hurdle.width + self.tolerance * 2.0;
+
+                left < stance.width && right > 0.0 && height < hurdle.high + self.tolerance &&
+                height + stance.height > hurdle.low - self.tolerance
+            })
+    }
+
+    /// Ticks until every hurdle is behind the player.
+    fn duration(&self) -> f32 {
+        self.hurdles
+            .iter()
+            .map(|hurdle| {
+                     (hurdle.at + hurdle.width + self.tolerance * 2.0) / (self.speed + hurdle.speed)
+                 })
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Searches a sequence of moves clearing every hurdle of the layout,
+/// starting on the ground a full jump before the hurdles reach `at`. Jumps
+/// are simulated tick by tick with `JumpPhysics::step`, with every hold
+/// length between a tap and a full jump. Slides only start from the ground
+/// and the player stands still through their cooldown, so a layout passing
+/// here never needs a fast-fall, a jump out of a slide, coyote time or an
+/// air jump.
+pub fn solve(physics: &JumpPhysics, layout: &Layout) -> Option<Vec<Move>> {
+    // Without gravity nothing ever lands
+    if physics.gravity <= 0.0 {
+        return None;
+    }
+
+    let runway = physics.airtime();
+    let ticks = runway + layout.duration().ceil().max(0.0) as u32 + 1;
+    let time = |tick: u32| tick as f32 - runway as f32;
+    let longest_move = layout
+        .slide
+        .map_or(0, |slide| slide.ticks + slide.cooldown)
+        .max(physics.airtime());
+    let standing = &layout.standing;
+
+    // Search over the ticks the player stands on the ground, keeping the
+    // moves that got there.
+    let mut reached: Vec<Option<Vec<Move>>> = vec![None; (ticks + longest_move) as usize + 1];
+    let mut queue = VecDeque::new();
+
+    if layout.collides(time(0), 0.0, standing) {
+        return None;
+    }
+
+    reached[0] = Some(Vec::new());
+    queue.push_back(0);
+
+    while let Some(tick) = queue.pop_front() {
+        if tick >= ticks {
+            return reached[tick as usize].clone();
+        }
+
+        let mut landings = Vec::new();
+
+        if !layout.collides(time(tick + 1), 0.0, standing) {
+            landings.push((tick + 1, None));
+        }
+
+        for hold in 0..physics.longest_hold() + 1 {
+            let (mut position_y, mut velocity_y) = (physics.ground_y, -physics.jump_velocity);
+            let mut now = tick;
+
+            loop {
+                now += 1;
+
+                if now - tick > hold {
+                    velocity_y = physics.release(velocity_y);
+                }
+
+                let landed = physics.step(&mut position_y, &mut velocity_y, 1.0);
+
+                if layout.collides(time(now), physics.ground_y - position_y, standing) {
+                    break;
+                }
+
+                if landed.is_some() {
+                    landings.push((now, Some(Move::Jump { tick, hold })));
+                    break;
+                }
+            }
+        }
+
+        if let Some(slide) = layout.slide {
+            let end = tick + slide.ticks + slide.cooldown;
+            let clear = (tick + 1..end + 1).all(|now| {
+                let stance = if now - tick <= slide.ticks {
+                    &slide.stance
+                } else {
+                    standing
+                };
+
+                !layout.collides(time(now), 0.0, stance)
+            });
+
+            if clear {
+                landings.push((end, Some(Move::Slide { tick })));
+            }
+        }
+
+        // Waiting costs nothing and any other move costs one, so the first
+        // solution found uses as few moves as possible.
+        for (landing, action) in landings {
+            let mut moves = reached[tick as usize].clone().unwrap();
+            moves.extend(action);
+
+            let better = reached[landing as usize]
+                .as_ref()
+                .is_none_or(|existing| moves.len() < existing.len());
+
+            if better {
+                reached[landing as usize] = Some(moves);
+
+                if action.is_some() {
+                    queue.push_back(landing);
+                } else {
+                    queue.push_front(landing);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve, Hurdle, Layout, Move, Movement, Stance};
+
+    /// A layout of the default jump against `hurdles`, without sliding.
+    fn layout(hurdles: &[Hurdle]) -> Layout<'_> {
+        Layout {
+            hurdles,
+            speed: 8.0,
+            standing: Stance {
+                offset: 0.0,
+                width: 66.0,
+                height: 135.0,
+            },
+            slide: None,
+            tolerance: 4.0,
+        }
+    }
+
+    fn hurdle(at: f32, high: f32) -> Hurdle {
+        Hurdle {
+            at,
+            width: 50.0,
+            low: 0.0,
+            high,
+            speed: 0.0,
+        }
+    }
+
+    #[test]
+    fn clears_a_low_stack_with_one_jump() {
+        let physics = Movement::new(400.0).jump;
+        let hurdles = [hurdle(300.0, 100.0)];
+        let moves = solve(&physics, &layout(&hurdles)).unwrap();
+
+        assert_eq!(moves.len(), 1);
+        assert!(matches!(moves[0], Move::Jump { .. }));
+    }
+
+    #[test]
+    fn rejects_a_stack_higher_than_any_jump() {
+        let physics = Movement::new(400.0).jump;
+        let hurdles = [hurdle(300.0, 400.0)];
+
+        assert_eq!(solve(&physics, &layout(&hurdles)), None);
+    }
+}
@@ -0,0 +1,120 @@
+use collision::Hitbox;
+use obstacles::GROUND;
+use sprites::Animator;
+use powerups::Power;
+
+/// Scale the pickup sprites are drawn at.
+pub const SCALE: i32 = 2;
+
+const SIZE: f32 = (16 * SCALE) as f32;
+/// Height above the ground of pickups the player runs through.
+pub const RUN_HEIGHT: f32 = 60.0;
+
+/// What a pickup is worth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collectible {
+    Coin,
+    Gem,
+    /// Grants a power instead of coins.
+    Power(Power),
+}
+
+impl Collectible {
+    pub fn value(&self) -> u32 {
+        match *self {
+            Collectible::Coin => 1,
+            Collectible::Gem => 5,
+            Collectible::Power(_) => 0,
+        }
+    }
+
+    pub fn animation(&self) -> &'static str {
+        match *self {
+            Collectible::Coin => "coin",
+            Collectible::Gem => "gem",
+            Collectible::Power(power) => power.name(),
+        }
+    }
+}
+
+/// A pickup of a chunk, `offset` pixels after its start with its middle
+/// `height` pixels above the ground.
+#[derive(Clone, Copy, Debug)]
+pub struct Spawn {
+    pub offset: f32,
+    pub height: f32,
+    pub kind: Collectible,
+}
+
+/// `count` coins along a parabola `width` pixels long peaking `height`
+/// pixels above the running line, the shape of a jump over whatever sits
+/// in its middle. With `gem` the coin at the top is a gem.
+pub fn arc(offset: f32, count: u32, width: f32, height: f32, gem: bool) -> Vec<Spawn> {
+    (0..count)
+        .map(|index| {
+            let along = if count > 1 {
+                index as f32 / (count - 1) as f32
+            } else {
+                0.5
+            };
+
+            Spawn {
+                offset: offset + width * along,
+                height: RUN_HEIGHT + height * 4.0 * along * (1.0 - along),
+                kind: if gem && index == count / 2 {
+                    Collectible::Gem
+                } else {
+                    Collectible::Coin
+                },
+            }
+        })
+        .collect()
+}
+
+/// `count` coins on the running line, `spacing` pixels apart. With `gem`
+/// the last one is a gem.
+pub fn row(offset: f32, count: u32, spacing: f32, gem: bool) -> Vec<Spawn> {
+    (0..count)
+        .map(|index| {
+            Spawn {
+                offset: offset + spacing * index as f32,
+                height: RUN_HEIGHT,
+                kind: if gem && index + 1 == count {
+                    Collectible::Gem
+                } else {
+                    Collectible::Coin
+                },
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+pub struct Pickup {
+    pub kind: Collectible,
+    pub position_x: f32,
+    pub height: f32,
+    pub animator: Animator,
+}
+
+impl Pickup {
+    pub fn new(kind: Collectible, position_x: f32, height: f32) -> Self {
+        Self {
+            kind,
+            position_x,
+            height,
+            animator: Animator::new(kind.animation()),
+        }
+    }
+
+    /// Where the sprite is drawn.
+    pub fn position(&self) -> (i32, i32) {
+        (self.position_x as i32, (GROUND as f32 - self.height - SIZE / 2.0) as i32)
+    }
+
+    pub fn hitbox(&self) -> Hitbox {
+        let (x, y) = self.position();
+
+        Hitbox::new(x as f32 + 4.0, y as f32 + 4.0, SIZE - 8.0, SIZE - 8.0)
+    }
+}
@@ -0,0 +1,252 @@
+use std::path::Path;
+use std::collections::HashMap;
+
+use data;
+
+/// Every power-up the game knows. Adding one means a variant here, its line
+/// in the `.powerups` file and what it changes in `Power::apply`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Power {
+    /// Absorbs hits, one per charge.
+    Shield,
+    /// Pulls pickups within `radius` towards the player.
+    Magnet,
+    /// Scales the world speed by `amount`.
+    SlowMotion,
+    /// Multiplies the points of every obstacle cleared by `amount`.
+    Multiplier,
+}
+
+impl Power {
+    pub const ALL: [Power; 4] = [Power::Shield, Power::Magnet, Power::SlowMotion, Power::Multiplier];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Power::Shield => "shield",
+            Power::Magnet => "magnet",
+            Power::SlowMotion => "slowmo",
+            Power::Multiplier => "multiplier",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "shield" => Some(Power::Shield),
+            "magnet" => Some(Power::Magnet),
+            "slowmo" => Some(Power::SlowMotion),
+            "multiplier" => Some(Power::Multiplier),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, tuning: &Tuning, stacks: u32, modifiers: &mut Modifiers) {
+        match *self {
+            Power::Shield => {}
+            Power::Magnet => {
+                modifiers.magnet = Some((tuning.radius * stacks as f32, tuning.amount));
+            }
+            Power::SlowMotion => modifiers.world_scale *= tuning.amount,
+            Power::Multiplier => {
+                modifiers.multiplier *= (tuning.amount as u32).pow(stacks);
+            }
+        }
+    }
+}
+
+/// What picking up a power that is already active does.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Stacking {
+    /// Starts its duration over.
+    Refresh,
+    /// Adds a full duration to what is left.
+    Extend,
+    /// Adds a stack up to `max_stacks` and starts its duration over.
+    Stack,
+}
+
+/// How a power behaves, from its line of the `.powerups` file.
+#[derive(Clone)]
+pub struct Tuning {
+    /// In ticks.
+    pub duration: f32,
+    pub stacking: Stacking,
+    pub max_stacks: u32,
+    /// Hits absorbed before it runs out, zero for powers that don't.
+    pub charges: u32,
+    pub amount: f32,
+    pub radius: f32,
+}
+
+/// A power currently running.
+#[derive(Clone)]
+pub struct Active {
+    pub power: Power,
+    /// Ticks left.
+    pub remaining: f32,
+    pub stacks: u32,
+    pub charges: u32,
+}
+
+/// Everything the active powers change, read by the game every tick.
+#[derive(Clone, Copy, Debug)]
+pub struct Modifiers {
+    pub world_scale: f32,
+    /// Radius and speed pickups are pulled at.
+    pub magnet: Option<(f32, f32)>,
+    pub multiplier: u32,
+}
+
+impl Modifiers {
+    pub fn new() -> Self {
+        Self {
+            world_scale: 1.0,
+            magnet: None,
+            multiplier: 1,
+        }
+    }
+}
+
+/// Tuning of every power and the ones running, loaded from a `.powerups`
+/// file:
+///
+/// ```text
+/// power shield duration=900 stacking=refresh charges=1
+/// power magnet duration=600 stacking=extend radius=220 amount=9
+/// ```
+pub struct PowerUps {
+    pub tuning: HashMap<String, Tuning>,
+    pub active: Vec<Active>,
+}
+
+impl PowerUps {
+    pub fn new() -> Self {
+        Self {
+            tuning: HashMap::new(),
+            active: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut powerups = PowerUps::new();
+
+        for entry in data::load(path)? {
+            if entry.keyword != "power" {
+                return Err(format!("line {}: unknown keyword `{}`", entry.line, entry.keyword));
+            }
+
+            let name: String = entry.arg(0)?;
+
+            if Power::parse(&name).is_none() {
+                return Err(format!("line {}: unknown power `{}`", entry.line, name));
+            }
+
+            let stacking = match entry.option("stacking").unwrap_or("refresh") {
+                "refresh" => Stacking::Refresh,
+                "extend" => Stacking::Extend,
+                "stack" => Stacking::Stack,
+                other => return Err(format!("line {}: unknown stacking `{}`", entry.line, other)),
+            };
+
+            powerups
+                .tuning
+                .insert(name,
+                        Tuning {
+                            duration: entry.option_or("duration", 600.0)?,
+                            stacking,
+                            max_stacks: entry.option_or("max_stacks", 1)?,
+                            charges: entry.option_or("charges", 0)?,
+                            amount: entry.option_or("amount", 1.0)?,
+                            radius: entry.option_or("radius", 0.0)?,
+                        });
+        }
+
+        Ok(powerups)
+    }
+
+    pub fn tuning(&self, power: Power) -> &Tuning {
+        self.tuning
+            .get(power.name())
+            .unwrap_or_else(|| panic!("missing tuning for power `{}`", power.name()))
+    }
+
+    /// Starts a power or stacks it on the running one.
+    pub fn grant(&mut self, power: Power) {
+        let tuning = self.tuning(power).clone();
+
+        match self.active.iter_mut().find(|active| active.power == power) {
+            Some(active) => {
+                match tuning.stacking {
+                    Stacking::Refresh => active.remaining = tuning.duration,
+                    Stacking::Extend => active.remaining += tuning.duration,
+                    Stacking::Stack => {
+                        active.stacks = (active.stacks + 1).min(tuning.max_stacks.max(1));
+                        active.remaining = tuning.duration;
+                    }
+                }
+
+                active.charges = active.charges.max(tuning.charges);
+            }
+            None => {
+                self.active
+                    .push(Active {
+                              power,
+                              remaining: tuning.duration,
+                              stacks: 1,
+                              charges: tuning.charges,
+                          })
+            }
+        }
+    }
+
+    /// Counts every power down by `step` ticks. Returns the ones that ran
+    /// out, for the game to react to.
+    pub fn update(&mut self, step: f32) -> Vec<Power> {
+        for active in self.active.iter_mut() {
+            active.remaining -= step;
+        }
+
+        let expired = self.active
+            .iter()
+            .filter(|active| active.remaining <= 0.0)
+            .map(|active| active.power)
+            .collect();
+
+        self.active.retain(|active| active.remaining > 0.0);
+        expired
+    }
+
+    /// Uses a charge of the first power that has one left, the hit is then
+    /// ignored. A power out of charges expires on the next update.
+    pub fn absorb_hit(&mut self) -> Option<Power> {
+        let active = self.active.iter_mut().find(|active| active.charges > 0)?;
+
+        active.charges -= 1;
+
+        if active.charges == 0 {
+            active.remaining = 0.0;
+        }
+
+        Some(active.power)
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        let mut modifiers = Modifiers::new();
+
+        for active in &self.active {
+            active
+                .power
+                .apply(self.tuning(active.power), active.stacks, &mut modifiers);
+        }
+
+        modifiers
+    }
+
+    /// Fraction of its duration a running power has left, for timers.
+    pub fn fraction(&self, active: &Active) -> f32 {
+        (active.remaining / self.tuning(active.power).duration).clamp(0.0, 1.0)
+    }
+
+    pub fn is_active(&self, power: Power) -> bool {
+        self.active.iter().any(|active| active.power == power)
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use save::{self, Save};
+use engine::RegistryItem;
+
+/// Where every profile keeps its save, one file per profile.
+pub const DIRECTORY: &str = "./saves";
+/// Profile created when there is none, the old global save becoming it.
+pub const DEFAULT: &str = "player";
+
+const EXTENSION: &str = "data";
+/// Remembers the profile picked last between launches.
+const SELECTED: &str = "./saves/selected";
+const MAX_NAME: usize = 16;
+/// As many as the profiles screen lists.
+const MAX_PROFILES: usize = 8;
+
+pub fn path(name: &str) -> PathBuf {
+    Path::new(DIRECTORY).join(format!("{}.{}", name, EXTENSION))
+}
+
+/// Names are used as file names, so they are kept to letters, digits, `-`
+/// and `_`.
+pub fn validate(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAME {
+        return Err(format!("names are 1 to {} characters long", MAX_NAME));
+    }
+
+    let allowed = |character: char| {
+        character.is_ascii_alphanumeric() || character == '-' || character == '_'
+    };
+
+    if !name.chars().all(allowed) {
+        return Err("names only use letters, digits, - and _".into());
+    }
+
+    Ok(())
+}
+
+/// Makes sure there is at least one profile, adopting the save kept before
+/// profiles existed.
+pub fn ensure() -> Result<(), String> {
+    fs::create_dir_all(DIRECTORY)
+        .map_err(|err| format!("could not create {}: {}", DIRECTORY, err))?;
+
+    if !list()?.is_empty() {
+        return Ok(());
+    }
+
+    let legacy = Path::new(save::LEGACY_PATH);
+
+    if legacy.exists() {
+        fs::rename(legacy, path(DEFAULT))
+            .map_err(|err| format!("could not move {}: {}", legacy.display(), err))
+    } else {
+        Save::new().write(&path(DEFAULT))
+    }
+}
+
+/// Names of every profile, sorted.
+pub fn list() -> Result<Vec<String>, String> {
+    let entries = fs::read_dir(DIRECTORY)
+        .map_err(|err| format!("could not read {}: {}", DIRECTORY, err))?;
+
+    let mut names = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == EXTENSION))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect::<Vec<String>>();
+
+    names.sort();
+    Ok(names)
+}
+
+pub fn create(name: &str) -> Result<(), String> {
+    validate(name)?;
+
+    if path(name).exists() {
+        return Err(format!("`{}` already exists", name));
+    }
+
+    if list()?.len() >= MAX_PROFILES {
+        return Err(format!("there can't be more than {} profiles", MAX_PROFILES));
+    }
+
+    Save::new().write(&path(name))
+}
+
+pub fn rename(from: &str, to: &str) -> Result<(), String> {
+    validate(to)?;
+
+    if path(to).exists() {
+        return Err(format!("`{}` already exists", to));
+    }
+
+    fs::rename(path(from), path(to))
+        .map_err(|err| format!("could not rename `{}`: {}", from, err))?;
+
+    let backup = save::backup_path(&path(from));
+
+    if backup.exists() {
+        fs::rename(backup, save::backup_path(&path(to)))
+            .map_err(|err| format!("could not rename the backup of `{}`: {}", from, err))?;
+    }
+
+    Ok(())
+}
+
+/// Deletes a profile and its save with its backup, the last one can't be.
+pub fn delete(name: &str) -> Result<(), String> {
+    if list()?.len() < 2 {
+        return Err("the last profile can't be deleted".into());
+    }
+
+    fs::remove_file(path(name))
+        .map_err(|err| format!("could not delete `{}`: {}", name, err))?;
+
+    let backup = save::backup_path(&path(name));
+
+    if backup.exists() {
+        fs::remove_file(backup)
+            .map_err(|err| format!("could not delete the backup of `{}`: {}", name, err))?;
+    }
+
+    Ok(())
+}
+
+/// The profile picked in the registry, else the one picked last time, else
+/// the first one.
+pub fn current(registry: &HashMap<String, RegistryItem>) -> String {
+    if let Some(RegistryItem::Text(name)) = registry.get("profile") {
+        return name.clone();
+    }
+
+    let names = list().unwrap_or_default();
+
+    fs::read_to_string(SELECTED)
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| names.contains(name))
+        .or_else(|| names.first().cloned())
+        .unwrap_or_else(|| DEFAULT.to_string())
+}
+
+/// Picks the profile every save goes to from now on. It is picked even when
+/// it can't be remembered for the next launch.
+pub fn select(name: &str, registry: &mut HashMap<String, RegistryItem>) -> Result<(), String> {
+    registry.insert("profile".into(), RegistryItem::Text(name.to_string()));
+
+    fs::write(SELECTED, name)
+        .map_err(|err| format!("could not remember profile `{}`: {}", name, err))
+}
+
+/// Save of the current profile.
+pub fn load(registry: &HashMap<String, RegistryItem>) -> Result<Save, String> {
+    let name = current(registry);
+
+    Save::load(&path(&name))
+        .map_err(|err| format!("could not load profile `{}`: {}", name, err))
+}
@@ -0,0 +1,511 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+
+use data::{self, Entry};
+
+/// Where the single save was kept before profiles, see `profiles::ensure`.
+pub const LEGACY_PATH: &str = "./save.data";
+
+/// Version of the format written. Saves of older versions are upgraded by
+/// `MIGRATIONS` when loaded.
+pub const VERSION: u32 = 2;
+
+/// Upgrades the entries of a version to the next, the first one taking
+/// version 1 to 2. Adding a version means a function here and its changes
+/// in `Save::from_entries` and `Save::encode`.
+const MIGRATIONS: [fn(Vec<Entry>) -> Vec<Entry>; 1] = [runs_into_totals];
+
+/// Version 1 kept the number of runs on its own line, version 2 keeps it
+/// with the other lifetime totals.
+fn runs_into_totals(entries: Vec<Entry>) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .map(|mut entry| {
+            if entry.keyword == "runs" {
+                entry.keyword = "total".into();
+                entry.args.insert(0, "runs".into());
+            }
+
+            entry
+        })
+        .collect()
+}
+
+/// FNV-1a of the contents under the header, enough to notice a save that
+/// was cut short or edited by hand.
+fn checksum(body: &str) -> u32 {
+    body.bytes()
+        .fold(0x811c_9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Copy of the save as it was before the last write, loaded when the save
+/// itself is corrupted.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Progress of a profile kept between runs, stored as a data file under a
+/// header with the version of the format and the checksum of the rest:
+///
+/// ```text
+/// save     2 checksum=5f1d3a07
+/// coins    120
+/// best     distance 48210
+/// total    runs 14
+/// unlocked tall_order
+/// owned    runner crimson
+/// stock    continue 2
+/// setting  difficulty hard
+/// ```
+///
+/// Version 1 saves have no header.
+pub struct Save {
+    /// Coins banked by every finished run.
+    pub coins: u64,
+    /// Highest value of every run stat, by name.
+    pub best: HashMap<String, u64>,
+    /// Every run stat added up over all runs, by name, along with `runs`.
+    pub totals: HashMap<String, u64>,
+    /// Ids of the achievements unlocked, in the order they were.
+    pub unlocked: Vec<String>,
+    /// Skins bought, by character and skin name.
+    pub owned: Vec<(String, String)>,
+    /// Consumables bought and not used yet, by name.
+    pub stock: HashMap<String, u64>,
+    /// What the profile overrides of the defaults, like its difficulty.
+    pub settings: HashMap<String, String>,
+    /// Why the save itself couldn't be read, when this was loaded from its
+    /// backup. Never written.
+    pub restored: Option<String>,
+}
+
+impl Save {
+    pub fn new() -> Self {
+        Self {
+            coins: 0,
+            best: HashMap::new(),
+            totals: HashMap::new(),
+            unlocked: Vec::new(),
+            owned: Vec::new(),
+            stock: HashMap::new(),
+            settings: HashMap::new(),
+            restored: None,
+        }
+    }
+
+    /// Loads a save, a missing file being a fresh one. A corrupted save
+    /// falls back to its backup, noting why in `restored`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Save::new());
+        }
+
+        let err = match read(path).and_then(|source| Save::decode(&source)) {
+            Ok(save) => return Ok(save),
+            Err(err) => format!("{}: {}", path.display(), err),
+        };
+
+        let backup = backup_path(path);
+
+        if !backup.exists() {
+            return Err(err);
+        }
+
+        match read(&backup).and_then(|source| Save::decode(&source)) {
+            Ok(mut save) => {
+                save.restored = Some(err);
+                Ok(save)
+            }
+            Err(backup_err) => Err(format!("{}, and its backup {}", err, backup_err)),
+        }
+    }
+
+    /// Parses a save of any known version.
+    pub fn decode(source: &str) -> Result<Self, String> {
+        let (version, body, offset) = match source.lines().next().map(data::parse) {
+            Some(ref header) if header.first().is_some_and(|entry| entry.keyword == "save") => {
+                let header = &header[0];
+                let body = source.split_once('\n').map(|(_, body)| body).unwrap_or("");
+                let expected = header
+                    .option("checksum")
+                    .and_then(|checksum| u32::from_str_radix(checksum, 16).ok())
+                    .ok_or("line 1: missing checksum")?;
+
+                if checksum(body) != expected {
+                    return Err("checksum mismatch, the save is corrupted".into());
+                }
+
+                (header.arg(0)?, body, 1)
+            }
+            _ => (1, source, 0),
+        };
+
+        if version == 0 || version > VERSION {
+            return Err(format!("unknown version {}, the latest is {}", version, VERSION));
+        }
+
+        let mut entries = data::parse(body);
+
+        for entry in entries.iter_mut() {
+            entry.line += offset;
+        }
+
+        for migration in &MIGRATIONS[version as usize - 1..] {
+            entries = migration(entries);
+        }
+
+        Save::from_entries(entries)
+    }
+
+    /// Reads the entries of a save of the latest version.
+    fn from_entries(entries: Vec<Entry>) -> Result<Self, String> {
+        let mut save = Save::new();
+
+        for entry in entries {
+            match entry.keyword.as_str() {
+                "coins" => save.coins = entry.arg(0)?,
+                "best" => {
+                    save.best.insert(entry.arg(0)?, entry.arg(1)?);
+                }
+                "total" => {
+                    save.totals.insert(entry.arg(0)?, entry.arg(1)?);
+                }
+                "unlocked" => save.unlocked.push(entry.arg(0)?),
+                "owned" => save.owned.push((entry.arg(0)?, entry.arg(1)?)),
+                "stock" => {
+                    save.stock.insert(entry.arg(0)?, entry.arg(1)?);
+                }
+                "setting" => {
+                    save.settings.insert(entry.arg(0)?, entry.arg(1)?);
+                }
+                other => return Err(format!("line {}: unknown keyword `{}`", entry.line, other)),
+            }
+        }
+
+        Ok(save)
+    }
+
+    /// Lifetime total of a stat, zero when never recorded.
+    pub fn total(&self, name: &str) -> u64 {
+        self.totals.get(name).cloned().unwrap_or(0)
+    }
+
+    pub fn add_total(&mut self, name: &str, value: u64) {
+        *self.totals.entry(name.into()).or_insert(0) += value;
+    }
+
+    pub fn runs(&self) -> u64 {
+        self.total("runs")
+    }
+
+    pub fn setting(&self, key: &str) -> Option<&str> {
+        self.settings.get(key).map(|value| value.as_str())
+    }
+
+    pub fn stock(&self, name: &str) -> u64 {
+        self.stock.get(name).cloned().unwrap_or(0)
+    }
+
+    /// Uses one of a consumable, failing when there is none left.
+    pub fn take(&mut self, name: &str) -> Result<(), String> {
+        match self.stock.get(name).cloned() {
+            Some(count) if count > 1 => {
+                self.stock.insert(name.into(), count - 1);
+            }
+            Some(1) => {
+                self.stock.remove(name);
+            }
+            _ => return Err(format!("no {} left", name)),
+        }
+
+        Ok(())
+    }
+
+    pub fn owns(&self, character: &str, skin: &str) -> bool {
+        self.owned
+            .iter()
+            .any(|owned| owned.0 == character && owned.1 == skin)
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.iter().any(|unlocked| unlocked == id)
+    }
+
+    /// The save as written to disk, header included.
+    pub fn encode(&self) -> String {
+        let mut body = format!("coins    {}\n", self.coins);
+
+        let counts = [("best", &self.best), ("total", &self.totals), ("stock", &self.stock)];
+
+        for (keyword, values) in &counts {
+            let mut values = values.iter().collect::<Vec<(&String, &u64)>>();
+
+            values.sort();
+
+            for (name, value) in values {
+                body.push_str(&format!("{:<8} {} {}\n", keyword, name, value));
+            }
+        }
+
+        for id in &self.unlocked {
+            body.push_str(&format!("unlocked {}\n", id));
+        }
+
+        for (character, skin) in &self.owned {
+            body.push_str(&format!("owned    {} {}\n", character, skin));
+        }
+
+        let mut settings = self.settings.iter().collect::<Vec<(&String, &String)>>();
+
+        settings.sort();
+
+        for (key, value) in settings {
+            body.push_str(&format!("setting  {} {}\n", key, value));
+        }
+
+        format!("save     {} checksum={:08x}\n{}", VERSION, checksum(&body), body)
+    }
+
+    /// Loads the save at `path`, changes it and writes it back. Nothing is
+    /// written when `change` fails, and the write replaces the file in one
+    /// go, so coins are never spent without what they bought or twice.
+    pub fn transact<F>(path: &Path, change: F) -> Result<Self, String>
+        where F: FnOnce(&mut Save) -> Result<(), String>
+    {
+        let mut save = Save::load(path)?;
+
+        change(&mut save)?;
+        save.write(path)?;
+        Ok(save)
+    }
+
+    /// Writes the save next to the file it replaces then swaps them, so a
+    /// crash halfway leaves the old save whole. The old one is kept as the
+    /// backup, unless it doesn't load.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let mut temporary = path.as_os_str().to_owned();
+
+        temporary.push(".tmp");
+
+        let temporary = PathBuf::from(temporary);
+        let describe = |err: ::std::io::Error| format!("could not write {}: {}", path.display(), err);
+
+        File::create(&temporary)
+            .and_then(|mut file| {
+                file.write_all(self.encode().as_bytes())?;
+                file.sync_all()
+            })
+            .map_err(describe)?;
+
+        let previous = read(path).and_then(|source| Save::decode(&source));
+
+        if previous.is_ok() {
+            fs::copy(path, backup_path(path)).map_err(describe)?;
+        }
+
+        fs::rename(&temporary, path).map_err(describe)
+    }
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    let mut source = String::new();
+
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut source))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::{Save, VERSION, backup_path, read};
+
+    fn fixture(name: &str) -> String {
+        read(&Path::new(env!("CARGO_MANIFEST_DIR"))
+                  .join("tests/fixtures/saves")
+                  .join(name))
+                .unwrap()
+    }
+
+    /// A file in a directory of its own, for tests writing saves.
+    fn scratch(test: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("endless-jumper-{}-{}", test, ::std::process::id()));
+
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory.join("profile.data")
+    }
+
+    #[test]
+    fn loads_the_latest_version() {
+        let save = Save::decode(&fixture("v2.data")).unwrap();
+
+        assert_eq!(save.coins, 120);
+        assert_eq!(save.runs(), 14);
+        assert_eq!(save.best.get("distance"), Some(&48210));
+        assert_eq!(save.total("jumps"), 1830);
+        assert_eq!(save.unlocked, vec!["first_steps", "tall_order"]);
+        assert_eq!(save.setting("difficulty"), Some("hard"));
+    }
+
+    #[test]
+    fn migrates_saves_without_a_version() {
+        let save = Save::decode(&fixture("v1.data")).unwrap();
+
+        assert_eq!(save.coins, 57);
+        assert_eq!(save.runs(), 6);
+        assert_eq!(save.total("jumps"), 412);
+        assert_eq!(save.best.get("score"), Some(&95));
+        assert!(save.is_unlocked("first_steps"));
+        assert_eq!(save.setting("difficulty"), Some("easy"));
+    }
+
+    #[test]
+    fn migrates_the_first_wallet_only_saves() {
+        let save = Save::decode(&fixture("v1_wallet.data")).unwrap();
+
+        assert_eq!(save.coins, 33);
+        assert_eq!(save.runs(), 0);
+    }
+
+    #[test]
+    fn rejects_a_corrupted_save() {
+        let err = Save::decode(&fixture("corrupted.data")).err().unwrap();
+
+        assert!(err.contains("checksum"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_a_newer_version() {
+        let err = Save::decode(&fixture("future.data")).err().unwrap();
+
+        assert!(err.contains("unknown version"), "{}", err);
+    }
+
+    #[test]
+    fn reports_lines_of_the_file() {
+        let err = Save::decode(&fixture("unknown_keyword.data")).err().unwrap();
+
+        assert_eq!(err, "line 3: unknown keyword `gems`");
+    }
+
+    #[test]
+    fn round_trips() {
+        let save = Save::decode(&fixture("v1.data")).unwrap();
+        let encoded = save.encode();
+
+        assert!(encoded.starts_with(&format!("save     {} ", VERSION)));
+        assert_eq!(Save::decode(&encoded).unwrap().encode(), encoded);
+    }
+
+    #[test]
+    fn a_failed_transaction_writes_nothing() {
+        let path = scratch("transaction");
+        let mut save = Save::new();
+
+        save.coins = 100;
+        save.write(&path).unwrap();
+
+        let spent = Save::transact(&path, |save| {
+            save.coins -= 60;
+            save.add_total("runs", 1);
+            Ok(())
+        });
+
+        assert_eq!(spent.unwrap().coins, 40);
+
+        let failed = Save::transact(&path, |save| {
+            save.coins = 0;
+            Err("not enough coins".into())
+        });
+
+        assert!(failed.is_err());
+        assert_eq!(Save::load(&path).unwrap().coins, 40);
+        assert_eq!(Save::load(&path).unwrap().runs(), 1);
+    }
+
+    #[test]
+    fn consumables_run_out() {
+        let mut save = Save::new();
+
+        save.stock.insert("continue".into(), 2);
+
+        assert!(save.take("continue").is_ok());
+        assert!(save.take("continue").is_ok());
+        assert!(save.take("continue").is_err());
+        assert!(save.take("shield").is_err());
+        assert!(!save.encode().contains("stock"));
+    }
+
+    #[test]
+    fn a_missing_save_is_a_fresh_one() {
+        let path = scratch("missing");
+        let save = Save::load(&path).unwrap();
+
+        assert_eq!(save.coins, 0);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn writes_atomically_and_keeps_a_backup() {
+        let path = scratch("backup");
+        let mut save = Save::new();
+
+        save.coins = 10;
+        save.write(&path).unwrap();
+        assert!(!backup_path(&path).exists());
+
+        save.coins = 20;
+        save.write(&path).unwrap();
+
+        assert_eq!(Save::load(&path).unwrap().coins, 20);
+        assert_eq!(Save::load(&backup_path(&path)).unwrap().coins, 10);
+        assert!(!path.with_extension("data.tmp").exists());
+    }
+
+    #[test]
+    fn falls_back_to_the_backup() {
+        let path = scratch("fallback");
+        let mut save = Save::new();
+
+        save.coins = 10;
+        save.write(&path).unwrap();
+        save.coins = 20;
+        save.write(&path).unwrap();
+
+        fs::write(&path, fixture("corrupted.data")).unwrap();
+
+        let restored = Save::load(&path).unwrap();
+
+        assert_eq!(restored.coins, 10);
+        assert!(restored.restored.is_some());
+        assert!(save.restored.is_none());
+    }
+
+    #[test]
+    fn a_corrupted_save_does_not_replace_the_backup() {
+        let path = scratch("keep_backup");
+        let mut save = Save::new();
+
+        save.coins = 10;
+        save.write(&path).unwrap();
+        save.coins = 20;
+        save.write(&path).unwrap();
+
+        fs::write(&path, fixture("corrupted.data")).unwrap();
+        save.coins = 30;
+        save.write(&path).unwrap();
+
+        assert_eq!(Save::load(&backup_path(&path)).unwrap().coins, 10);
+    }
+}
@@ -0,0 +1,208 @@
+extern crate sdl2;
+
+use std::path::Path;
+use std::collections::HashMap;
+
+use self::sdl2::pixels::Color;
+use self::sdl2::event::Event;
+use self::sdl2::keyboard::Keycode;
+use self::sdl2::ttf;
+use self::sdl2::render::Texture;
+use self::sdl2::mixer::channel;
+
+use helpers;
+use achievements;
+use profiles;
+use save::Save;
+use stats::Stat;
+use engine::{Scene, Loop, Context};
+
+/// Vertical offset of the back button, below the list.
+const BACK_Y: i32 = 240;
+
+/// Lifetime stats of the save and every achievement, unlocked or with the
+/// progress made towards it.
+pub struct Achievements {
+    textures: HashMap<String, Texture>,
+    over_back: bool,
+    /// Text of every line under the title, the profile first.
+    lines: Vec<String>,
+}
+
+impl Scene for Achievements {
+    fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+            over_back: false,
+            lines: Vec::new(),
+        }
+    }
+
+    fn on_event(&mut self, event: Event, ctx: &mut Context) -> Loop {
+        match event {
+            Event::Quit { .. } => Loop::Break,
+            Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                Loop::GoToScene("menu".into())
+            }
+            Event::MouseMotion { x, y, .. } => {
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, BACK_Y)) {
+                    if !self.over_back {
+                        channel(1)
+                            .play(ctx.sounds.get("menu").unwrap(), 0)
+                            .unwrap();
+                    }
+
+                    self.over_back = true;
+                } else {
+                    self.over_back = false;
+                }
+                Loop::Continue
+            }
+            Event::MouseButtonUp { x, y, .. } => {
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, BACK_Y)) {
+                    Loop::GoToScene("menu".into())
+                } else {
+                    Loop::Continue
+                }
+            }
+            _ => Loop::Continue,
+        }
+    }
+
+    fn on_load(&mut self, ctx: &mut Context) -> Loop {
+        let mut font = ctx.ttf_context
+            .load_font(Path::new("./assets/font.ttf"), 128)
+            .unwrap();
+
+        font.set_style(ttf::STYLE_BOLD);
+
+        let title_surface = font.render("Achievements")
+            .blended(Color::RGBA(0, 0, 0, 255))
+            .unwrap();
+
+        self.textures
+            .insert("title".into(),
+                    ctx.renderer
+                        .create_texture_from_surface(&title_surface)
+                        .unwrap());
+
+        let back_surface = font.render("BACK")
+            .blended(Color::RGBA(0, 0, 0, 255))
+            .unwrap();
+
+        self.textures
+            .insert("back".into(),
+                    ctx.renderer
+                        .create_texture_from_surface(&back_surface)
+                        .unwrap());
+
+        font.set_style(ttf::STYLE_NORMAL);
+
+        let (profile, failure) = match profiles::load(&ctx.registry) {
+            Ok(profile) => (profile, None),
+            Err(err) => (Save::new(), Some(err)),
+        };
+
+        let mut lines = vec![(format!("{}   Runs {}   Time played {}   Best distance {}   Jumps {}",
+                                      profiles::current(&ctx.registry),
+                                      profile.runs(),
+                                      Stat::Duration.format(profile.total(Stat::Duration.name())),
+                                      Stat::Distance.format(profile.best
+                                                                .get(Stat::Distance.name())
+                                                                .cloned()
+                                                                .unwrap_or(0)),
+                                      profile.total(Stat::Jumps.name())),
+                              Color::RGBA(0, 0, 0, 255))];
+
+        for achievement in achievements::load(Path::new("./assets/game.achievements")).unwrap() {
+            let line = if profile.is_unlocked(&achievement.id) {
+                (format!("[x] {}", achievement.title), Color::RGBA(0, 140, 0, 255))
+            } else {
+                let progress = match achievement.saved_progress(&profile) {
+                    Some(progress) => {
+                        format!("   {} / {}",
+                                achievement.counter.format(progress.min(achievement.target)),
+                                achievement.counter.format(achievement.target))
+                    }
+                    None => String::new(),
+                };
+
+                (format!("[ ] {}{}", achievement.title, progress), Color::RGBA(120, 120, 120, 255))
+            };
+
+            lines.push(line);
+        }
+
+        if let Some(err) = failure {
+            lines.push((err, Color::RGBA(200, 0, 0, 255)));
+        }
+
+        self.lines.clear();
+
+        for (index, (text, color)) in lines.into_iter().enumerate() {
+            let surface = font.render(&text).blended(color).unwrap();
+
+            self.textures
+                .insert(format!("line_{}", index),
+                        ctx.renderer
+                            .create_texture_from_surface(&surface)
+                            .unwrap());
+            self.lines.push(text);
+        }
+
+        Loop::Continue
+    }
+
+    fn on_tick(&mut self, ctx: &mut Context) -> Loop {
+        ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+        ctx.renderer.clear();
+
+        // Renders the title
+        {
+            let title = self.textures.get("title").unwrap();
+            let position = helpers::rect_centered(360, 40, 0, -250);
+
+            ctx.renderer
+                .copy(title, None, Some(position))
+                .unwrap();
+        }
+
+        // Renders the profile, then one achievement per line
+        for (index, line) in self.lines.iter().enumerate() {
+            let text = self.textures.get(&format!("line_{}", index)).unwrap();
+            let width = line.len() * 9;
+            let y = if index == 0 { -200 } else { -190 + 28 * index as i32 };
+            let position = helpers::rect_centered(width as i32, 18, 0, y);
+
+            ctx.renderer
+                .copy(text, None, Some(position))
+                .unwrap();
+        }
+
+        // Renders the back button
+        {
+            match self.over_back {
+                true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+                false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+            }
+
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(200, 60, 0, BACK_Y))
+                .unwrap();
+
+            ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(190, 50, 0, BACK_Y))
+                .unwrap();
+
+            let back = self.textures.get("back").unwrap();
+            let position = helpers::rect_centered(100, 20, 0, BACK_Y);
+
+            ctx.renderer
+                .copy(back, None, Some(position))
+                .unwrap();
+        }
+
+        Loop::Continue
+    }
+}
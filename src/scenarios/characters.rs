@@ -0,0 +1,336 @@
+extern crate sdl2;
+
+use std::path::Path;
+use std::collections::HashMap;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::pixels::Color;
+use self::sdl2::event::Event;
+use self::sdl2::keyboard::Keycode;
+use self::sdl2::ttf;
+use self::sdl2::image::LoadTexture;
+use self::sdl2::render::Texture;
+use self::sdl2::mixer::channel;
+
+use config;
+use helpers;
+use profiles;
+use save::Save;
+use sprites::{SpriteSheet, Animator};
+use characters::{self, Character};
+use shop;
+use engine::{Scene, Loop, Context};
+
+const CARD_Y: i32 = -80;
+const CARD_SPACING: i32 = 280;
+const SKINS_Y: i32 = 120;
+const SKIN_SPACING: i32 = 170;
+const BACK_Y: i32 = 240;
+
+/// Every character running in place with its stats, and the skins of the
+/// one picked, bought with the coins of the profile.
+pub struct Characters {
+    textures: HashMap<String, Texture>,
+    characters: Vec<Character>,
+    sheets: Vec<SpriteSheet>,
+    /// Shared by every preview, they all run in step.
+    animator: Animator,
+    /// Save of the current profile, as written by the last change.
+    profile: Save,
+    /// Outcome of the last click on a skin, and whether it failed.
+    status: Option<(String, bool)>,
+    over_back: bool,
+}
+
+impl Characters {
+    fn card(index: usize, count: usize) -> Rect {
+        let offset = (2 * index as i32 - (count as i32 - 1)) * CARD_SPACING / 2;
+
+        helpers::rect_centered(220, 220, offset, CARD_Y)
+    }
+
+    fn skin_button(index: usize, count: usize) -> Rect {
+        let offset = (2 * index as i32 - (count as i32 - 1)) * SKIN_SPACING / 2;
+
+        helpers::rect_centered(150, 40, offset, SKINS_Y)
+    }
+
+    fn selected(&self) -> (&Character, &str) {
+        let (character, skin) = characters::selected(&self.characters, &self.profile);
+
+        (character, &skin.name)
+    }
+
+    /// Wears a skin of a character, buying it first when the save doesn't
+    /// own it.
+    fn pick(&mut self, character: &Character, skin: usize, ctx: &mut Context) {
+        let item = shop::skin_item(character, &character.skins[skin]);
+        let path = profiles::path(&profiles::current(&ctx.registry));
+        let mut bought = false;
+        let result = Save::transact(&path, |save| {
+            if !characters::owns(save, character, &character.skins[skin]) {
+                item.buy(save)?;
+                bought = true;
+            }
+
+            item.equip(save);
+            Ok(())
+        });
+
+        match result {
+            Ok(save) => {
+                self.profile = save;
+                self.status = if bought {
+                    Some((format!("Bought {} for {} coins", item.label(), item.price), false))
+                } else {
+                    None
+                };
+            }
+            Err(err) => self.status = Some((err, true)),
+        }
+    }
+
+    /// The current frame of a character's run, as big as fits its card.
+    fn draw_preview(&mut self, index: usize, card: Rect, ctx: &mut Context) {
+        let sheet = &self.sheets[index];
+        let animation = sheet.animation("run");
+        let frame = self.animator.frame(animation).rect;
+        let fit = (160.0 / frame.width() as f32).min(150.0 / frame.height() as f32);
+        let (width, height) = ((frame.width() as f32 * fit) as u32,
+                               (frame.height() as f32 * fit) as u32);
+        let destination = Rect::new(card.x() + (card.width() - width) as i32 / 2,
+                                    card.y() + 170 - height as i32,
+                                    width,
+                                    height);
+
+        let (selected, skin) = self.selected();
+        let (red, green, blue) = if selected.name == self.characters[index].name {
+            selected.skin(skin).color
+        } else {
+            (255, 255, 255)
+        };
+
+        let texture = self.textures.get_mut(&sheet.texture).unwrap();
+
+        texture.set_color_mod(red, green, blue);
+        ctx.renderer
+            .copy_ex(texture,
+                     Some(frame),
+                     Some(destination),
+                     0.0,
+                     None,
+                     animation.flip_horizontal,
+                     animation.flip_vertical)
+            .unwrap();
+    }
+}
+
+impl Scene for Characters {
+    fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+            characters: Vec::new(),
+            sheets: Vec::new(),
+            animator: Animator::new("run"),
+            profile: Save::new(),
+            status: None,
+            over_back: false,
+        }
+    }
+
+    fn on_event(&mut self, event: Event, ctx: &mut Context) -> Loop {
+        match event {
+            Event::Quit { .. } => Loop::Break,
+            Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                Loop::GoToScene("menu".into())
+            }
+            Event::MouseMotion { x, y, .. } => {
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, BACK_Y)) {
+                    if !self.over_back {
+                        channel(1)
+                            .play(ctx.sounds.get("menu").unwrap(), 0)
+                            .unwrap();
+                    }
+
+                    self.over_back = true;
+                } else {
+                    self.over_back = false;
+                }
+                Loop::Continue
+            }
+            Event::MouseButtonUp { x, y, .. } => {
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, BACK_Y)) {
+                    return Loop::GoToScene("menu".into());
+                }
+
+                let count = self.characters.len();
+
+                if let Some(index) = (0..count)
+                       .find(|index| helpers::point_colliding_rect(x, y, &Characters::card(*index, count))) {
+                    let character = self.characters[index].clone();
+
+                    self.pick(&character, 0, ctx);
+                    return Loop::Continue;
+                }
+
+                let skins = self.selected().0.skins.len();
+
+                if let Some(index) = (0..skins)
+                       .find(|index| {
+                                 helpers::point_colliding_rect(x, y, &Characters::skin_button(*index, skins))
+                             }) {
+                    let character = self.selected().0.clone();
+
+                    self.pick(&character, index, ctx);
+                }
+
+                Loop::Continue
+            }
+            _ => Loop::Continue,
+        }
+    }
+
+    fn on_load(&mut self, ctx: &mut Context) -> Loop {
+        let mut font = ctx.ttf_context
+            .load_font(Path::new("./assets/font.ttf"), 128)
+            .unwrap();
+
+        font.set_style(ttf::STYLE_BOLD);
+
+        for &(name, text) in &[("title", "Characters"), ("back", "BACK")] {
+            let surface = font.render(text)
+                .blended(Color::RGBA(0, 0, 0, 255))
+                .unwrap();
+
+            self.textures
+                .insert(name.into(),
+                        ctx.renderer
+                            .create_texture_from_surface(&surface)
+                            .unwrap());
+        }
+
+        self.characters = characters::load(Path::new("./assets/game.characters")).unwrap();
+        self.sheets.clear();
+
+        for character in &self.characters {
+            let sheet = SpriteSheet::load(Path::new(&character.sheet)).unwrap();
+
+            self.textures
+                .insert(sheet.texture.clone(),
+                        ctx.renderer
+                            .load_texture(Path::new(&sheet.texture))
+                            .unwrap());
+            self.sheets.push(sheet);
+        }
+
+        self.animator = Animator::new("run");
+        self.status = None;
+        self.profile = profiles::load(&ctx.registry).unwrap_or_else(|err| {
+            self.status = Some((err, true));
+            Save::new()
+        });
+
+        Loop::Continue
+    }
+
+    fn on_tick(&mut self, ctx: &mut Context) -> Loop {
+        ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+        ctx.renderer.clear();
+
+        self.animator.advance(config::TICK_MILLIS);
+
+        // Renders the title
+        {
+            let title = self.textures.get("title").unwrap();
+
+            ctx.renderer
+                .copy(title, None, Some(helpers::rect_centered(300, 40, 0, -250)))
+                .unwrap();
+        }
+
+        helpers::draw_text(&format!("Coins: {}", self.profile.coins),
+                           Color::RGBA(0, 0, 0, 255),
+                           (0, -210),
+                           ctx);
+
+        // Renders every character, the picked one framed in red
+        let count = self.characters.len();
+        let selected = self.selected().0.name.clone();
+
+        for index in 0..count {
+            let card = Characters::card(index, count);
+            let character = self.characters[index].clone();
+
+            match character.name == selected {
+                true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+                false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+            }
+
+            ctx.renderer.draw_rect(card).unwrap();
+            self.draw_preview(index, card, ctx);
+
+            let center_x = card.x() + card.width() as i32 / 2 - config::WINDOW_WIDTH as i32 / 2;
+
+            helpers::draw_text(&character.name.to_uppercase(),
+                               Color::RGBA(0, 0, 0, 255),
+                               (center_x, CARD_Y + 92),
+                               ctx);
+            helpers::draw_text(&format!("Jump x{:.2}   Speed x{:.2}",
+                                        character.jump,
+                                        character.speed),
+                               Color::RGBA(90, 90, 90, 255),
+                               (center_x, CARD_Y + 130),
+                               ctx);
+        }
+
+        // Renders the skins of the picked character, with their price until
+        // they are bought
+        let (character, skin) = self.selected();
+        let character = character.clone();
+        let worn = skin.to_string();
+
+        for (index, skin) in character.skins.iter().enumerate() {
+            let button = Characters::skin_button(index, character.skins.len());
+            let label = if characters::owns(&self.profile, &character, skin) {
+                skin.name.to_uppercase()
+            } else {
+                format!("{} {}", skin.name, skin.price)
+            };
+
+            match skin.name == worn {
+                true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+                false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+            }
+
+            ctx.renderer.draw_rect(button).unwrap();
+
+            let (red, green, blue) = skin.color;
+            let center_x = button.x() + button.width() as i32 / 2 - config::WINDOW_WIDTH as i32 / 2;
+
+            ctx.renderer.set_draw_color(Color::RGB(red, green, blue));
+            ctx.renderer
+                .fill_rect(Rect::new(button.x() + 6, button.y() + 12, 16, 16))
+                .unwrap();
+
+            helpers::draw_text(&label, Color::RGBA(0, 0, 0, 255), (center_x + 10, SKINS_Y), ctx);
+        }
+
+        if let Some((ref message, failed)) = self.status {
+            let color = if failed {
+                Color::RGBA(200, 0, 0, 255)
+            } else {
+                Color::RGBA(0, 140, 0, 255)
+            };
+
+            helpers::draw_text(message, color, (0, SKINS_Y + 50), ctx);
+        }
+
+        helpers::draw_button(helpers::rect_centered(200, 60, 0, BACK_Y),
+                             self.textures.get("back").unwrap(),
+                             (100, 20),
+                             self.over_back,
+                             ctx);
+
+        Loop::Continue
+    }
+}
@@ -2,43 +2,141 @@ extern crate sdl2;
 extern crate rand;
 
 use std::path::Path;
-use std::collections::HashMap;
-use std::time::Instant;
+use std::collections::{HashMap, VecDeque};
 
-use self::sdl2::rect::Rect;
+use self::sdl2::rect::{Rect, Point};
 use self::sdl2::pixels::Color;
 use self::sdl2::event::Event;
-use self::sdl2::render::Texture;
+use self::sdl2::render::{Texture, BlendMode};
 use self::sdl2::image::LoadTexture;
 use self::sdl2::keyboard::Keycode;
 use self::sdl2::mixer::{Chunk, channel};
 use self::sdl2::ttf::STYLE_BOLD;
+use self::sdl2::gfx::primitives::DrawRenderer;
+use self::rand::{Rng, SeedableRng, XorShiftRng};
 
-use helpers;
+use config;
+use sprites::{SpriteSheet, Animator};
+use parallax::Parallax;
+use camera::Camera;
+use particles::ParticleSystem;
+use transitions::Transition;
+use collision::{CollisionWorld, Contact, Hitbox, LAYER_PLAYER, LAYER_OBSTACLE};
+use masks::{self, MaskSet, Placement};
+use console::Command;
+use difficulty::{self, Preset, Progress};
+use patterns::Patterns;
+use physics::{self, JumpPhysics, Movement, Hurdle, Layout, Slide, Stance};
+use obstacles::{self, Obstacle, Kind};
+use pickups::{self, Collectible, Pickup, Spawn};
+use powerups::{Power, PowerUps, Modifiers};
+use scoring::{Scoring, Rules, Category, Popup};
+use stats::{RunStats, Stat};
+use save::Save;
+use profiles;
+use achievements::{self, Achievement, Toast};
+use characters::{self, Character};
+use shop;
 use engine::{Scene, Context, Loop, RegistryItem};
 
-const MONTAINS_SCALE: u32 = 3;
-const GROUND_SCALE: u32 = 2;
 const PLAYER_SCALE: i32 = 3;
-const GRAVITY: f32 = 0.5;
-const ROCK_SCALE: i32 = 2;
-
-#[derive(Clone)]
-struct Obstacle {
-    pub rocks: u32,
-    pub position_x: f32,
-    pub timer: Instant,
-    pub pending_point: bool,
-}
+const PLAYER_FEET: f32 = (50 * PLAYER_SCALE) as f32;
+const GROUND_Y: f32 = 400.0;
+/// Room in pixels a generated chunk must leave around every stack.
+const JUMP_TOLERANCE: f32 = 4.0;
+const CAMERA_FOLLOW: f32 = 0.25;
+const DEATH_TICKS: u32 = 60;
+const DEATH_ZOOM: f32 = 2.5;
+/// Ticks a hit that cost a life leaves the player untouchable.
+const INVULNERABLE_TICKS: f32 = 90.0;
+/// Speed the world is pushed back at after a hit, in pixels per tick, and
+/// how much of it is left after every tick.
+const KNOCKBACK_SPEED: f32 = 14.0;
+const KNOCKBACK_DECAY: f32 = 0.85;
+const KNOCKBACK_HOP: f32 = 8.0;
+const PLAYER_BODY: usize = 0;
+
+const SLIDE_TICKS: u32 = 40;
+const SLIDE_COOLDOWN: u32 = 20;
+/// Downward speed given when sliding is pressed in the air.
+const FAST_FALL: f32 = 18.0;
+const MASK_SAMPLES: u32 = 4;
+const MAX_ROCKS: u32 = 11;
+const SPAWN_X: f32 = -80.0;
 
 pub struct Game {
     textures: HashMap<String, Texture>,
+    sheets: HashMap<String, SpriteSheet>,
+    masks: HashMap<String, MaskSet>,
+    player_animator: Animator,
+    /// Who the player runs as, picked in the profile.
+    character: Character,
+    background: Parallax,
+    camera: Camera,
+    particles: ParticleSystem,
+    dying: Option<u32>,
+    lives: u32,
+    /// Ticks left before the player can be hit again.
+    invulnerable: f32,
+    knockback: f32,
+    difficulty: Preset,
+    /// Pixels the world travelled since the run started.
+    distance: f32,
+    world_speed: f32,
+    /// Fraction of a tick simulated this tick, from the console's timescale.
+    step: f32,
+    movement: Movement,
+    god: bool,
+    rng: XorShiftRng,
     velocity_y: f32,
     position_y: f32,
     jumping: bool,
     released: bool,
+    /// Ticks since the player last stood on the ground.
+    airborne: f32,
+    air_jumps: u32,
+    /// Ticks left of a jump pressed too early, it happens on landing.
+    buffered: f32,
+    /// Set when the player fell into a pit, the ground no longer holds it.
+    falling: bool,
+    /// Ticks left of the current slide.
+    sliding: Option<f32>,
+    slide_cooldown: f32,
+    /// Set when sliding was pressed in the air, the slide starts on landing.
+    fast_falling: bool,
     obstacles: Vec<Obstacle>,
+    next_obstacle_id: usize,
+    patterns: Patterns,
+    /// Stacks of the current chunk still waiting for the world to travel
+    /// their offset.
+    pending: VecDeque<(f32, Kind)>,
+    chunk_name: String,
+    chunk_distance: f32,
+    chunk_after: f32,
+    pickups: Vec<Pickup>,
+    pending_pickups: VecDeque<Spawn>,
+    powerups: PowerUps,
+    /// What the running powers change, updated at the start of every tick.
+    modifiers: Modifiers,
+    collisions: CollisionWorld,
+    contacts: Vec<Contact>,
+    /// Obstacles cleared, what difficulty curves and chunks are driven by.
     points: u32,
+    scoring: Scoring,
+    popups: Vec<Popup>,
+    stats: RunStats,
+    achievements: Vec<Achievement>,
+    /// The save as it was when the run started, with the achievements
+    /// unlocked since.
+    profile: Save,
+    /// Unlock notifications, shown one after the other.
+    toasts: VecDeque<Toast>,
+    /// Continues bought in the shop, one gets the player back up instead of
+    /// ending the run.
+    continues: u64,
+    /// Collected this run, added to the save's wallet when the run ends.
+    coins: u32,
+    banked: bool,
 }
 
 impl Game {
@@ -46,245 +144,1123 @@ impl Game {
         let mut font = ctx.fonts.get_mut("default".into()).unwrap();
         font.set_style(STYLE_BOLD);
 
-        let surface = font.render(&format!("Points: {}", self.points))
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .unwrap();
+        let counters = [format!("Score: {}", self.scoring.total()),
+                        format!("Coins: {}", self.coins),
+                        format!("Combo: {} x{}", self.scoring.combo, self.scoring.multiplier())];
+
+        for (index, text) in counters.iter().enumerate() {
+            let surface = font.render(text)
+                .blended(Color::RGBA(0, 0, 0, 255))
+                .unwrap();
+
+            let mut texture = ctx.renderer
+                .create_texture_from_surface(&surface)
+                .unwrap();
+
+            let destination = Rect::new(10, 10 + 24 * index as i32, 11 * text.len() as u32, 20);
+
+            ctx.renderer
+                .copy(&mut texture, None, Some(destination))
+                .unwrap();
+        }
+    }
+
+    /// Reacts to a power running out.
+    fn power_expired(&mut self, power: Power, ctx: &mut Context) {
+        let (x, y) = self.player_center();
+
+        self.particles.emit("power_down", x, y, &mut ctx.thread_rng);
+
+        if power == Power::SlowMotion {
+            self.camera.add_trauma(0.2);
+        }
+    }
+
+    /// A hit a power absorbed: the obstacle breaks instead of the player.
+    fn absorb(&mut self, contact: Contact, ctx: &mut Context) {
+        let (hit_x, hit_y) = (contact.overlap.x + contact.overlap.width / 2.0,
+                              contact.overlap.y + contact.overlap.height / 2.0);
+
+        self.obstacles.retain(|obstacle| obstacle.id != contact.other);
+        self.scoring.break_combo();
+        self.camera.add_trauma(0.4);
+        self.particles.emit("debris", hit_x, hit_y, &mut ctx.thread_rng);
+        self.particles.emit("impact", hit_x, hit_y, &mut ctx.thread_rng);
+    }
+
+    /// A hit with lives left: the player loses one, is thrown back and can't
+    /// be hit again for a moment.
+    fn hurt(&mut self, contact: Contact, ctx: &mut Context) {
+        let (hit_x, hit_y) = (contact.overlap.x + contact.overlap.width / 2.0,
+                              contact.overlap.y + contact.overlap.height / 2.0);
+
+        // The obstacle that hit the player gives no point once passed
+        for obstacle in self.obstacles.iter_mut() {
+            if obstacle.id == contact.other {
+                obstacle.pending_point = false;
+            }
+        }
+
+        self.lives -= 1;
+        self.scoring.break_combo();
+        self.invulnerable = INVULNERABLE_TICKS;
+        self.knockback = KNOCKBACK_SPEED;
+
+        // Hopping back out of a pit the player fell in
+        self.position_y = self.position_y.min(GROUND_Y);
+        self.velocity_y = -KNOCKBACK_HOP;
+        self.jumping = true;
+        self.fast_falling = false;
+
+        if self.sliding.take().is_some() {
+            self.slide_cooldown = SLIDE_COOLDOWN as f32;
+        }
+
+        self.camera.add_trauma(0.6);
+        self.particles.emit("impact", hit_x, hit_y, &mut ctx.thread_rng);
+    }
+
+    /// One heart per life of the difficulty, when it has more than one.
+    fn draw_lives(&self, ctx: &mut Context) {
+        let total = self.difficulty.lives;
+
+        if total < 2 {
+            return;
+        }
+
+        let sheet = self.sheets.get("pickups").unwrap();
+        let texture = self.textures.get(&sheet.texture).unwrap();
+
+        for index in 0..total {
+            let animation = if index < self.lives { "heart" } else { "heart_empty" };
+            let icon = sheet.animation(animation).frames[0].rect;
+            let x = config::WINDOW_WIDTH as i32 - 10 - 28 * (total - index) as i32;
+
+            ctx.renderer
+                .copy(texture, Some(icon), Some(Rect::new(x, 10, 24, 24)))
+                .unwrap();
+        }
+    }
+
+    /// Icon and remaining time of every running power, under the counters.
+    fn draw_powers(&self, ctx: &mut Context) {
+        let sheet = self.sheets.get("pickups").unwrap();
+        let texture = self.textures.get(&sheet.texture).unwrap();
+
+        for (index, active) in self.powerups.active.iter().enumerate() {
+            let y = 84 + 28 * index as i32;
+            let icon = sheet.animation(active.power.name()).frames[0].rect;
+            let fraction = self.powerups.fraction(active);
+
+            ctx.renderer
+                .copy(texture, Some(icon), Some(Rect::new(10, y, 24, 24)))
+                .unwrap();
+
+            ctx.renderer.set_draw_color(Color::RGB(0, 0, 0));
+            ctx.renderer.fill_rect(Rect::new(40, y + 8, 104, 10)).unwrap();
+            ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+            ctx.renderer
+                .fill_rect(Rect::new(42, y + 10, (100.0 * fraction) as u32, 6))
+                .unwrap();
+        }
+    }
 
+    /// Stats kept elsewhere during the run.
+    fn update_stats(&mut self) {
+        self.stats.set(Stat::Score, self.scoring.total() as u64);
+        self.stats.set(Stat::Distance, self.distance.max(0.0) as u64);
+        self.stats.set(Stat::Coins, self.coins as u64);
+    }
+
+    /// Unlocks the achievements the run just met, each with a toast.
+    fn check_achievements(&mut self) {
+        for achievement in &self.achievements {
+            if self.profile.is_unlocked(&achievement.id) ||
+               achievement.progress(&self.stats, &self.profile) < achievement.target {
+                continue;
+            }
+
+            self.profile.unlocked.push(achievement.id.clone());
+            self.toasts
+                .push_back(Toast::new(format!("Achievement unlocked: {}", achievement.title)));
+        }
+    }
+
+    /// The oldest toast at the top of the screen, fading in and out.
+    fn draw_toasts(&mut self, ctx: &mut Context) {
+        if let Some(toast) = self.toasts.front_mut() {
+            toast.age += self.step;
+        }
+
+        if self.toasts.front().is_some_and(|toast| toast.age >= Toast::LIFETIME) {
+            self.toasts.pop_front();
+        }
+
+        let toast = match self.toasts.front() {
+            Some(toast) => toast,
+            None => return,
+        };
+
+        let font = ctx.fonts.get_mut("default").unwrap();
+        let surface = font.render(&toast.text)
+            .blended(Color::RGBA(255, 255, 255, 255))
+            .unwrap();
         let mut texture = ctx.renderer
             .create_texture_from_surface(&surface)
             .unwrap();
+        let width = 9 * toast.text.len() as u32;
+        let x = (config::WINDOW_WIDTH - width) as i32 / 2;
 
-        let destination = Rect::new(10, 10, 100, 20);
+        ctx.renderer.set_blend_mode(BlendMode::Blend);
+        ctx.renderer
+            .set_draw_color(Color::RGBA(0, 0, 0, toast.alpha() / 4 * 3));
+        ctx.renderer
+            .fill_rect(Rect::new(x - 12, 40, width + 24, 34))
+            .unwrap();
+        ctx.renderer.set_blend_mode(BlendMode::None);
 
+        texture.set_alpha_mod(toast.alpha());
         ctx.renderer
-            .copy(&mut texture, None, Some(destination))
+            .copy(&texture, None, Some(Rect::new(x, 48, width, 18)))
             .unwrap();
     }
 
-    fn is_colliding(&mut self, ctx: &mut Context) -> bool {
-        let mut colliding = false;
+    /// Ends the run, once: adds it to the save's wallet, lifetime totals and
+    /// achievements, and publishes its stats next to the personal bests they
+    /// beat or not, before recording the new ones.
+    fn finish_run(&mut self, ctx: &mut Context) {
+        if self.banked {
+            return;
+        }
 
-        for mut obstacle in self.obstacles.iter_mut() {
-            let rock_y = 447 + (32 * ROCK_SCALE) - (obstacle.rocks as i32 * (32 - 15));
-            let rock_x = obstacle.position_x + (32 * ROCK_SCALE) as f32;
-            let player_y = self.position_y + (50 * PLAYER_SCALE) as f32;
+        self.update_stats();
 
-            if self.jumping {
-                if rock_x >= 560.0 && rock_x <= 570.0 && rock_y <= player_y as i32 {
-                    colliding = true;
+        if self.stats.cause.is_empty() {
+            self.stats.cause = "Gave up".into();
+        }
+
+        self.stats.publish(&mut ctx.registry);
+
+        let path = profiles::path(&profiles::current(&ctx.registry));
+        let stats = &self.stats;
+        let unlocked = &self.profile.unlocked;
+        let result = Save::transact(&path, |save| {
+            save.coins += stats.get(Stat::Coins);
+            save.add_total("runs", 1);
+            ctx.registry
+                .insert("wallet".into(), RegistryItem::Number(save.coins as usize));
+
+            for stat in &Stat::ALL {
+                let best = save.best.get(stat.name()).cloned().unwrap_or(0);
+
+                ctx.registry
+                    .insert(format!("best_{}", stat.name()),
+                            RegistryItem::Number(best as usize));
+                save.best
+                    .insert(stat.name().into(), best.max(stats.get(*stat)));
+                save.add_total(stat.name(), stats.get(*stat));
+            }
+
+            for id in unlocked {
+                if !save.is_unlocked(id) {
+                    save.unlocked.push(id.clone());
                 }
-            } else {
-                if rock_x >= 530.0 && rock_x <= 540.0 && rock_y <= player_y as i32 {
-                    colliding = true;
+            }
+
+            Ok(())
+        });
+
+        // Shown on the game over screen, as the run ends here
+        match result {
+            Ok(_) => {
+                ctx.registry.remove("save_error");
+            }
+            Err(err) => {
+                ctx.registry
+                    .insert("save_error".into(),
+                            RegistryItem::Text(format!("Could not save the run: {}", err)));
+            }
+        }
+
+        self.banked = true;
+    }
+
+    /// Grants one of every power bought in the shop. They are taken from
+    /// the save before the run starts, so a crash can't use them twice.
+    fn grant_starting_powers(&mut self, ctx: &Context) {
+        let stocked = Power::ALL
+            .iter()
+            .cloned()
+            .filter(|power| self.profile.stock(power.name()) > 0)
+            .collect::<Vec<Power>>();
+
+        if stocked.is_empty() {
+            return;
+        }
+
+        let path = profiles::path(&profiles::current(&ctx.registry));
+        let result = Save::transact(&path, |save| {
+            for power in &stocked {
+                save.take(power.name())?;
+            }
+
+            Ok(())
+        });
+
+        match result {
+            Ok(_) => {
+                for power in stocked {
+                    self.powerups.grant(power);
                 }
             }
+            Err(err) => {
+                self.toasts
+                    .push_back(Toast::new(format!("Could not use the starting powers: {}", err)))
+            }
+        }
+    }
 
-            if rock_x > 570.0 && obstacle.pending_point {
+    /// A hit that would end the run with a continue left: it's used up and
+    /// the player gets back up as if it had one more life.
+    fn use_continue(&mut self, contact: Contact, ctx: &mut Context) {
+        let path = profiles::path(&profiles::current(&ctx.registry));
+
+        match Save::transact(&path, |save| save.take(shop::CONTINUE)) {
+            Ok(save) => self.continues = save.stock(shop::CONTINUE),
+            Err(err) => {
+                self.toasts
+                    .push_back(Toast::new(format!("Could not use a continue: {}", err)));
+                self.continues = 0;
+                self.die(contact, ctx);
+                return;
+            }
+        }
+
+        self.lives += 1;
+        self.hurt(contact, ctx);
+        self.toasts
+            .push_back(Toast::new(format!("Continue used, {} left", self.continues)));
+    }
+
+    fn collect_pickups(&mut self, ctx: &mut Context) {
+        let player = self.player_hitbox();
+        let (collected, left): (Vec<Pickup>, Vec<Pickup>) = self.pickups
+            .drain(..)
+            .partition(|pickup| pickup.hitbox().intersects(&player));
+
+        self.pickups = left;
+
+        for pickup in collected {
+            let hitbox = pickup.hitbox();
+            let effect = match pickup.kind {
+                Collectible::Power(power) => {
+                    self.powerups.grant(power);
+                    "power_up"
+                }
+                _ => "sparkle",
+            };
+
+            self.coins += pickup.kind.value();
+            self.particles
+                .emit(effect,
+                      hitbox.x + hitbox.width / 2.0,
+                      hitbox.y + hitbox.height / 2.0,
+                      &mut ctx.thread_rng);
+
+            channel(3)
+                .play(ctx.sounds.get("coin").unwrap(), 0)
+                .unwrap();
+        }
+
+        ctx.registry
+            .insert("coins".into(), RegistryItem::Number(self.coins as usize));
+    }
+
+    fn player_hitbox(&self) -> Hitbox {
+        let (left, top, width, height) = if self.sliding.is_some() {
+            self.character.slide_hitbox
+        } else {
+            self.character.hitbox
+        };
+
+        Hitbox::new(500.0 + left,
+                    self.position_y + self.character.offset_y as f32 + top,
+                    width,
+                    height)
+    }
+
+    /// Whether the middle of the player's feet is above a pit.
+    fn over_hole(&self) -> bool {
+        let player = self.player_hitbox();
+        let feet = player.x + player.width / 2.0;
+
+        self.obstacles
+            .iter()
+            .filter_map(|obstacle| obstacle.kind.hole(obstacle.position_x))
+            .any(|(left, right)| feet > left && feet < right)
+    }
+
+    /// Jumps from the ground, within coyote time of leaving it, or in the
+    /// air while air jumps are left. Returns whether the player jumped.
+    fn jump(&mut self, ctx: &mut Context) -> bool {
+        if self.dying.is_some() {
+            return false;
+        }
+
+        if !self.jumping && self.airborne <= self.movement.coyote as f32 {
+            self.velocity_y = -self.movement.jump.jump_velocity;
+        } else if self.air_jumps > 0 {
+            self.air_jumps -= 1;
+            self.velocity_y = -self.movement.air_jump_velocity;
+        } else {
+            return false;
+        }
+
+        // Jumping cuts a slide short, its cooldown still applies
+        if self.sliding.take().is_some() {
+            self.slide_cooldown = SLIDE_COOLDOWN as f32;
+        }
+
+        self.jumping = true;
+        self.fast_falling = false;
+        self.buffered = 0.0;
+        self.stats.add(Stat::Jumps, 1);
+
+        // A buffered jump whose key is already up is a tap
+        if self.released {
+            self.velocity_y = self.movement.jump.release(self.velocity_y);
+        }
+
+        let (x, _) = self.player_center();
+        self.particles
+            .emit("jump", x, self.position_y + PLAYER_FEET, &mut ctx.thread_rng);
+
+        channel(2)
+            .play(ctx.sounds.get("jump").unwrap(), 0)
+            .unwrap();
+
+        true
+    }
+
+    fn start_slide(&mut self, ctx: &mut Context) {
+        if self.sliding.is_some() || self.slide_cooldown > 0.0 {
+            return;
+        }
+
+        let (x, _) = self.player_center();
+
+        self.sliding = Some(SLIDE_TICKS as f32);
+        self.particles
+            .emit("slide", x, self.position_y + PLAYER_FEET, &mut ctx.thread_rng);
+    }
+
+    /// Counts down the current slide, then its cooldown.
+    fn update_slide(&mut self) {
+        match self.sliding {
+            Some(ticks) if ticks > self.step => self.sliding = Some(ticks - self.step),
+            Some(_) => {
+                self.sliding = None;
+                self.slide_cooldown = SLIDE_COOLDOWN as f32;
+            }
+            None => self.slide_cooldown = (self.slide_cooldown - self.step).max(0.0),
+        }
+    }
+
+    /// Registers this tick's movement of every entity, swept from where it
+    /// was before `physics` moved it.
+    fn update_collisions(&mut self, previous_y: f32) {
+        let displacement_y = self.position_y - previous_y;
+        let player = self.player_hitbox();
+
+        self.collisions.clear();
+        self.collisions
+            .add(PLAYER_BODY,
+                 player.translate(0.0, -displacement_y),
+                 (0.0, displacement_y),
+                 LAYER_PLAYER,
+                 LAYER_OBSTACLE);
+
+        for obstacle in &self.obstacles {
+            let delta = self.obstacle_delta(obstacle);
+
+            self.collisions
+                .add(obstacle.id,
+                     obstacle.hitbox().translate(-delta, 0.0),
+                     (delta, 0.0),
+                     LAYER_OBSTACLE,
+                     LAYER_PLAYER);
+        }
+    }
+
+    /// Narrow phase for a bounding box contact: tests the alpha masks of the
+    /// frames being drawn, from the moment the boxes met to the end of the
+    /// step so thin sprites can't tunnel through each other either. Pits
+    /// have nothing to test, touching their box means falling in.
+    fn pixels_touch(&self, contact: &Contact) -> bool {
+        let obstacle = match self.obstacles.iter().find(|obstacle| obstacle.id == contact.other) {
+            Some(obstacle) => obstacle,
+            None => return true,
+        };
+
+        if let Kind::Pit(_) = obstacle.kind {
+            return true;
+        }
+
+        let (sheet_name, _) = obstacle.kind.sprite();
+        let player_sheet = self.sheets.get("player").unwrap();
+        let obstacle_sheet = self.sheets.get(sheet_name).unwrap();
+        let player_animation = player_sheet.animation(&self.player_animator.animation);
+        let obstacle_animation = obstacle_sheet.animation(&obstacle.animator.animation);
+        let player_frame = self.player_animator.frame(player_animation).rect;
+        let obstacle_frame = obstacle.animator.frame(obstacle_animation).rect;
+
+        let (player_mask, obstacle_mask) = match (self.masks["player"].get(player_frame),
+                                                  self.masks[sheet_name].get(obstacle_frame)) {
+            (Some(player_mask), Some(obstacle_mask)) => (player_mask, obstacle_mask),
+            _ => return true,
+        };
+
+        let displacement_y = self.collisions
+            .body(PLAYER_BODY)
+            .map(|body| body.displacement.1)
+            .unwrap_or(0.0);
+
+        for sample in 0..MASK_SAMPLES + 1 {
+            let time = contact.time + (1.0 - contact.time) * sample as f32 / MASK_SAMPLES as f32;
+            let rewind = 1.0 - time;
+            let player = Placement {
+                mask: player_mask,
+                x: 500.0,
+                y: self.position_y + self.character.offset_y as f32 - displacement_y * rewind,
+                scale: self.character.scale,
+                flip_horizontal: player_animation.flip_horizontal,
+            };
+
+            for (x, y) in obstacle.kind.tiles() {
+                let tile = Placement {
+                    mask: obstacle_mask,
+                    x: obstacle.position_x + x as f32 - self.obstacle_delta(obstacle) * rewind,
+                    y: y as f32,
+                    scale: obstacles::SCALE,
+                    flip_horizontal: obstacle_animation.flip_horizontal,
+                };
+
+                if masks::overlaps(&player, &tile) {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn is_colliding(&mut self, ctx: &mut Context) -> bool {
+        let player = self.player_hitbox();
+        let contacts = self.collisions.query(PLAYER_BODY);
+
+        self.contacts = contacts
+            .into_iter()
+            .filter(|contact| self.pixels_touch(contact))
+            .collect();
+
+        let mut cleared = Vec::new();
+
+        for obstacle in self.obstacles.iter_mut() {
+            let hitbox = obstacle.hitbox();
+
+            if hitbox.x < player.right() && hitbox.right() > player.x {
+                let clearance = (hitbox.y - player.bottom()).max(player.y - hitbox.bottom());
+                obstacle.clearance = obstacle.clearance.min(clearance);
+            }
+
+            if obstacle.pending_point && hitbox.x > player.right() {
                 obstacle.pending_point = false;
-                self.points += 1;
+                cleared.push((obstacle.kind.size(), obstacle.clearance));
+            }
+        }
 
-                ctx.registry
-                    .insert("points".into(),
-                            RegistryItem::Number(self.points.clone() as usize));
+        for (size, clearance) in cleared {
+            let awards = self.scoring
+                .clear(size, clearance, self.modifiers.multiplier);
+            let (x, y) = self.player_center();
+
+            self.points += 1;
+            self.stats.clear_obstacle(size);
+
+            let combo = (self.scoring.combo as u64).max(self.stats.get(Stat::MaxCombo));
+            self.stats.set(Stat::MaxCombo, combo);
+
+            for (index, (category, points)) in awards.into_iter().enumerate() {
+                if category == Category::NearMisses {
+                    self.stats.add(Stat::NearMisses, 1);
+                }
+
+                let text = match category {
+                    Category::Obstacles => format!("+{}", points),
+                    _ => format!("{} +{}", category.label(), points),
+                };
+
+                self.popups
+                    .push(Popup::new(text, x, y - 100.0 - 24.0 * index as f32));
             }
+        }
+
+        self.publish_score(ctx);
+
+        !self.contacts.is_empty()
+    }
 
+    /// Total and breakdown of the score, for the game over screen.
+    fn publish_score(&self, ctx: &mut Context) {
+        ctx.registry
+            .insert("points".into(),
+                    RegistryItem::Number(self.scoring.total() as usize));
+
+        for category in &Category::ALL {
+            ctx.registry
+                .insert(format!("score_{}", category.name()),
+                        RegistryItem::Number(self.scoring.category(*category) as usize));
+        }
+    }
+
+    fn draw_popups(&mut self, ctx: &mut Context) {
+        let font = ctx.fonts.get_mut("default").unwrap();
+
+        for popup in &self.popups {
+            let surface = font.render(&popup.text)
+                .blended(Color::RGBA(40, 40, 40, 255))
+                .unwrap();
+            let mut texture = ctx.renderer
+                .create_texture_from_surface(&surface)
+                .unwrap();
+            let (x, y) = self.camera.to_screen(popup.x, popup.y);
+            let width = 9 * popup.text.len() as u32;
+
+            texture.set_alpha_mod(popup.alpha());
+            ctx.renderer
+                .copy(&texture, None, Some(Rect::new(x - width as i32 / 2, y, width, 18)))
+                .unwrap();
         }
+    }
 
-        colliding
+    /// How far the world moves this tick.
+    fn world_delta(&self) -> f32 {
+        (self.world_speed * self.world_scale() - self.knockback) * self.step
     }
 
-    fn add_obstacle(&mut self, ctx: &mut Context) {
-        let last_obstacle_position = self.obstacles
+    /// What the world speed is multiplied by, for powers and characters.
+    fn world_scale(&self) -> f32 {
+        self.modifiers.world_scale * self.character.speed
+    }
+
+    /// How far an obstacle moves this tick, some go faster than the world.
+    fn obstacle_delta(&self, obstacle: &Obstacle) -> f32 {
+        if self.dying.is_some() {
+            0.0
+        } else {
+            self.world_delta() + obstacle.kind.speed() * self.step
+        }
+    }
+
+    fn progress(&self) -> Progress {
+        Progress {
+            points: self.points,
+            distance: self.distance,
+        }
+    }
+
+    fn add_obstacle(&mut self) {
+        if self.pending.is_empty() {
+            self.next_chunk();
+        }
+
+        while let Some(&(offset, kind)) = self.pending.front() {
+            if offset > self.chunk_distance {
+                break;
+            }
+
+            self.pending.pop_front();
+            self.spawn_obstacle(kind, SPAWN_X + self.chunk_distance - offset);
+        }
+
+        while let Some(&spawn) = self.pending_pickups.front() {
+            if spawn.offset > self.chunk_distance {
+                break;
+            }
+
+            self.pending_pickups.pop_front();
+            self.pickups
+                .push(Pickup::new(spawn.kind,
+                                  SPAWN_X + self.chunk_distance - spawn.offset,
+                                  spawn.height));
+        }
+    }
+
+    /// Rolls the next chunk once the last one is a gap away, only keeping
+    /// chunks that can be cleared right after the previous stack.
+    fn next_chunk(&mut self) {
+        let progress = self.progress();
+        let gap = self.difficulty.gap(&progress) + self.chunk_after;
+        let last = self.obstacles.iter().last();
+
+        if last.is_some_and(|obstacle| obstacle.position_x < SPAWN_X + gap) {
+            return;
+        }
+
+        let (min, max) = self.difficulty.stacks(&progress);
+        let max = max.min(MAX_ROCKS);
+        let character = &self.character;
+        let player = Game::standing_hitbox(character);
+        let previous = last.map(|obstacle| obstacle.kind.hurdle(SPAWN_X + gap, &player));
+        // Powers only last a while, so chunks are checked without them
+        let (jump, speed) = (self.movement.jump, self.world_speed * character.speed);
+
+        let pattern = self.patterns
+            .generate(&mut self.rng, self.points, (min.min(max), max), |stacks| {
+                let hurdles = previous
+                    .iter()
+                    .cloned()
+                    .chain(Game::hurdles(stacks, &player))
+                    .collect::<Vec<Hurdle>>();
+
+                Game::clearable(&jump, &hurdles, speed, character)
+            });
+
+        self.pending = pattern.obstacles.into_iter().collect();
+        self.pending_pickups = pattern.pickups.into_iter().collect();
+        self.chunk_name = pattern.name;
+        self.chunk_distance = 0.0;
+        // Gaps add up, moving the next chunk further from the last obstacle
+        // until it can be cleared
+        self.chunk_after = if self.pending.is_empty() {
+            self.chunk_after + pattern.after
+        } else {
+            pattern.after
+        };
+    }
+
+    /// The character's hitbox standing on the ground, what the solver
+    /// checks hurdles against.
+    fn standing_hitbox(character: &Character) -> Hitbox {
+        let (left, top, width, height) = character.hitbox;
+
+        Hitbox::new(500.0 + left,
+                    GROUND_Y + character.offset_y as f32 + top,
+                    width,
+                    height)
+    }
+
+    /// How the character slides, its stance measured from the front of its
+    /// standing hitbox.
+    fn slide(character: &Character) -> Slide {
+        let (left, _, width, _) = character.hitbox;
+        let (slide_left, _, slide_width, slide_height) = character.slide_hitbox;
+
+        Slide {
+            ticks: SLIDE_TICKS,
+            cooldown: SLIDE_COOLDOWN,
+            stance: Stance {
+                offset: (left + width) - (slide_left + slide_width),
+                width: slide_width,
+                height: slide_height,
+            },
+        }
+    }
+
+    /// Obstacles of a chunk as they are spawned, the first one at `SPAWN_X`.
+    fn hurdles(obstacles: &[(f32, Kind)], player: &Hitbox) -> Vec<Hurdle> {
+        obstacles
             .iter()
-            .last()
-            .map(|obstacle| obstacle.position_x)
-            .unwrap_or(800.0);
+            .map(|&(offset, kind)| kind.hurdle(SPAWN_X - offset, player))
+            .collect()
+    }
+
+    fn clearable(jump: &JumpPhysics, hurdles: &[Hurdle], speed: f32, character: &Character) -> bool {
+        let layout = Layout {
+            hurdles,
+            speed,
+            standing: Stance {
+                offset: 0.0,
+                width: character.hitbox.2,
+                height: character.hitbox.3,
+            },
+            slide: Some(Game::slide(character)),
+            tolerance: JUMP_TOLERANCE,
+        };
+
+        physics::solve(jump, &layout).is_some()
+    }
 
-        if last_obstacle_position >= 800.0 {
-            self.obstacles
-                .push(Obstacle {
-                          position_x: -80.0,
-                          timer: Instant::now(),
-                          rocks: rand::sample(&mut ctx.thread_rng,
-                                              (1..12).collect::<Vec<u32>>(),
-                                              1)
-                                  .remove(0),
-                          pending_point: true,
-                      });
+    /// Checks every chunk of a file against every difficulty preset and
+    /// character, with the tallest stacks allowed, at a few points of a run.
+    /// Returns one line per chunk that can't be cleared.
+    pub fn validate_chunks(chunks: &Path,
+                           presets: &Path,
+                           movement: &Path,
+                           roster: &Path)
+                           -> Result<Vec<String>, String> {
+        let patterns = Patterns::load(chunks)?;
+        let presets = difficulty::load(presets)?;
+        let movement = Movement::load(movement, GROUND_Y)?;
+        let roster = characters::load(roster)?;
+        let mut failures = Vec::new();
+
+        for (preset, character) in presets
+                .iter()
+                .flat_map(|preset| roster.iter().map(move |character| (preset, character))) {
+            let mut jump = movement.jump;
+
+            jump.jump_velocity *= character.velocity_scale();
+
+            for &points in &[0, 10, 20, 40, 80] {
+                // Roughly one obstacle per point
+                let distance = points as f32 * preset.gap(&Progress { points, distance: 0.0 });
+                let progress = Progress { points, distance };
+                let (min, max) = preset.stacks(&progress);
+                let range = (min.min(max.min(MAX_ROCKS)), max.min(MAX_ROCKS));
+                let speed = preset.speed(&progress) * character.speed;
+
+                for chunk in patterns.chunks.iter().filter(|chunk| chunk.min_points <= points) {
+                    let stacks = chunk.resolve(range, |_, max| max);
+                    let hurdles = Game::hurdles(&stacks, &Game::standing_hitbox(character));
+
+                    if !Game::clearable(&jump, &hurdles, speed, character) {
+                        failures.push(format!("{} as {}: chunk `{}` at {} points, speed {:.2}, stacks {:?}",
+                                              preset.name,
+                                              character.name,
+                                              chunk.name,
+                                              points,
+                                              speed,
+                                              stacks));
+                    }
+                }
+            }
         }
+
+        Ok(failures)
+    }
+
+    fn spawn_obstacle(&mut self, kind: Kind, position_x: f32) {
+        self.obstacles
+            .push(Obstacle::new(self.next_obstacle_id, kind, position_x));
+        self.next_obstacle_id += 1;
     }
 
-    fn physics(&mut self, _ctx: &mut Context) {
-        for mut obstacle in self.obstacles.iter_mut() {
-            obstacle.position_x += 8.55;
+    fn physics(&mut self, ctx: &mut Context) {
+        let previous_y = self.position_y;
+        let world_delta = self.world_delta();
+
+        for index in 0..self.obstacles.len() {
+            let delta = self.obstacle_delta(&self.obstacles[index]);
+            self.obstacles[index].position_x += delta;
+        }
+
+        self.distance += world_delta;
+        self.scoring.travel(world_delta);
+
+        for popup in self.popups.iter_mut() {
+            popup.age += self.step;
+            popup.y -= self.step;
         }
 
+        self.popups.retain(|popup| popup.age < Popup::LIFETIME);
+        self.chunk_distance += world_delta;
+
+        self.background.update(world_delta);
+
         self.obstacles = self.obstacles
             .clone()
             .into_iter()
             .filter(|obstacle| obstacle.position_x <= 800.0)
             .collect::<Vec<Obstacle>>();
 
-        self.velocity_y += GRAVITY;
-        self.position_y += self.velocity_y;
+        let (center_x, center_y) = self.player_center();
+
+        for pickup in self.pickups.iter_mut() {
+            pickup.position_x += world_delta;
+
+            if let Some((radius, speed)) = self.modifiers.magnet {
+                let hitbox = pickup.hitbox();
+                let (x, y) = (hitbox.x + hitbox.width / 2.0, hitbox.y + hitbox.height / 2.0);
+                let distance = ((center_x - x).powi(2) + (center_y - y).powi(2)).sqrt();
+
+                if distance < radius && distance > 0.0 {
+                    let pull = (speed * self.step).min(distance) / distance;
+
+                    pickup.position_x += (center_x - x) * pull;
+                    pickup.height -= (center_y - y) * pull;
+                }
+            }
+        }
+
+        self.pickups.retain(|pickup| pickup.position_x <= 800.0);
+
+        let mut jump = self.movement.jump;
+
+        // Once dead the player keeps falling into whatever killed it, while
+        // invulnerable it runs over holes
+        if self.dying.is_none() {
+            self.falling = self.over_hole() && self.invulnerable <= 0.0;
+        }
+
+        if self.falling {
+            jump.ground_y = f32::INFINITY;
+        }
+
+        let landed = jump.step(&mut self.position_y, &mut self.velocity_y, self.step);
+
+        self.airborne += self.step;
+        self.buffered = (self.buffered - self.step).max(0.0);
+
+        if let Some(impact) = landed {
+            if self.jumping {
+                let (x, _) = self.player_center();
+
+                self.camera.add_trauma(impact / 40.0);
+                self.particles
+                    .emit("landing", x, 400.00 + PLAYER_FEET, &mut ctx.thread_rng);
+            }
 
-        if self.position_y > 400.00 {
             self.jumping = false;
-            self.position_y = 400.00;
-            self.velocity_y = 0.00;
-        }
-    }
-
-    fn draw_montains(&self, ctx: &mut Context) {
-        let montains_texture = self.textures.get("montains".into()).unwrap();
-        let millis = helpers::get_milliseconds(&ctx.timer.elapsed());
-        let window_height = (ctx.renderer.window().unwrap().size().1) as i32;
-        let image_width = (montains_texture.query().width * MONTAINS_SCALE) as i32;
-        let image_height = (montains_texture.query().height * MONTAINS_SCALE) as i32;
-        let x = ((millis % 15000) as i32 * image_width / 15000) as i32;
-
-        for n in 0..2 {
-            let offset = n as i32;
-            let start_at = x + (-(image_width));
-            let destination = Rect::new((image_width * offset + start_at) as i32,
-                                        window_height - image_height,
-                                        image_width as u32,
-                                        image_height as u32);
+
+            if self.fast_falling {
+                self.fast_falling = false;
+                self.start_slide(ctx);
+            }
+
+            self.airborne = 0.0;
+            self.air_jumps = self.movement.air_jumps;
+
+            if self.buffered > 0.0 {
+                self.jump(ctx);
+            }
+        }
+
+        self.update_slide();
+        self.update_collisions(previous_y);
+    }
+
+    fn draw_player(&self, ctx: &mut Context) {
+        let sheet = self.sheets.get("player").unwrap();
+        let player = self.textures.get(&sheet.texture).unwrap();
+
+        // Flashes while invulnerable
+        if (self.invulnerable as u32 / 6).is_multiple_of(2) {
+            sheet.draw(&mut ctx.renderer,
+                       &self.camera,
+                       player,
+                       &self.player_animator,
+                       (500, self.position_y as i32 + self.character.offset_y),
+                       self.character.scale);
+        }
+
+        if self.powerups.is_active(Power::Shield) {
+            let (x, y) = self.player_center();
+            let (x, y) = self.camera.to_screen(x, y);
+
             ctx.renderer
-                .copy(montains_texture, None, Some(destination))
+                .filled_circle(x as i16,
+                               y as i16,
+                               (90.0 * self.camera.zoom) as i16,
+                               Color::RGBA(90, 170, 255, 60))
                 .unwrap();
         }
     }
 
-    fn draw_player(&self, ctx: &mut Context) {
-        let player = self.textures.get("player".into()).unwrap();
-        let sprite_row = 3i32;
-        let millis = helpers::get_milliseconds(&ctx.timer.elapsed());
-        let sprite_column = ((millis % 800) / 100) as i32;
-        let image_width = 46i32;
-        let image_height = 50i32;
-
-        let sprite = Rect::new(sprite_column * image_width,
-                               sprite_row * image_height,
-                               image_width as u32,
-                               image_height as u32);
-
-
-        let destination = Rect::new(500,
-                                    self.position_y as i32,
-                                    (image_width * PLAYER_SCALE) as u32,
-                                    (image_height * PLAYER_SCALE) as u32);
-        ctx.renderer
-            .copy_ex(&player,
-                     Some(sprite),
-                     Some(destination),
-                     0.00,
-                     None,
-                     true,
-                     false)
-            .unwrap();
+    fn draw_pickups(&self, ctx: &mut Context) {
+        let sheet = self.sheets.get("pickups").unwrap();
+        let texture = self.textures.get(&sheet.texture).unwrap();
+
+        for pickup in &self.pickups {
+            sheet.draw(&mut ctx.renderer,
+                       &self.camera,
+                       texture,
+                       &pickup.animator,
+                       pickup.position(),
+                       pickups::SCALE);
+        }
     }
 
     fn draw_obstacles(&self, ctx: &mut Context) {
-        let rock = self.textures.get("rocks".into()).unwrap();
-        let sprite_row = 0i32;
-        let sprite_column = 3i32;
-        let image_width = 32i32;
-        let image_height = 32i32;
-        let sprite = Rect::new(sprite_column * image_width,
-                               sprite_row * image_height,
-                               image_width as u32,
-                               image_height as u32);
-
         for obstacle in &self.obstacles {
-            for n in 0..obstacle.rocks {
-                let destination = Rect::new(obstacle.position_x as i32,
-                                            447 + image_height - (n as i32 * (image_height - 15)),
-                                            (image_width * ROCK_SCALE) as u32,
-                                            (image_height * ROCK_SCALE) as u32);
-                ctx.renderer
-                    .copy_ex(&rock,
-                             Some(sprite),
-                             Some(destination),
-                             0.00,
-                             None,
-                             true,
-                             false)
-                    .unwrap();
-            }
-        }
-    }
-
-    fn draw_jump(&self, ctx: &mut Context) {
-        let player = self.textures.get("player".into()).unwrap();
-        let sprite_row = 0i32;
-        let sprite_column = {
-            if self.velocity_y < 0.00 { 6 } else { 7 }
-        } as i32;
-        let image_width = 46i32;
-        let image_height = 50i32;
-
-        let sprite = Rect::new(sprite_column * image_width,
-                               sprite_row * image_height,
-                               image_width as u32,
-                               image_height as u32);
-
-
-        let destination = Rect::new(500,
-                                    self.position_y as i32,
-                                    (image_width * PLAYER_SCALE) as u32,
-                                    (image_height * PLAYER_SCALE) as u32);
-        ctx.renderer
-            .copy_ex(&player,
-                     Some(sprite),
-                     Some(destination),
-                     0.00,
-                     None,
-                     true,
-                     false)
-            .unwrap();
+            let sheet = self.sheets.get(obstacle.kind.sprite().0).unwrap();
+            let texture = self.textures.get(&sheet.texture).unwrap();
+
+            for (x, y) in obstacle.kind.tiles() {
+                sheet.draw(&mut ctx.renderer,
+                           &self.camera,
+                           texture,
+                           &obstacle.animator,
+                           (obstacle.position_x as i32 + x, y),
+                           obstacles::SCALE);
+            }
+        }
     }
 
-    fn draw_forest(&self, ctx: &mut Context) {
-        let forest_texture = self.textures.get("forest".into()).unwrap();
-        let millis = helpers::get_milliseconds(&ctx.timer.elapsed());
-        let window_height = (ctx.renderer.window().unwrap().size().1) as i32;
-        let image_width = (forest_texture.query().width * MONTAINS_SCALE) as i32;
-        let image_height = (forest_texture.query().height * MONTAINS_SCALE) as i32;
-        let x = ((millis % 5000) as i32 * image_width / 5000) as i32;
-
-        for n in 0..3 {
-            let offset = n as i32;
-            let start_at = x + (-(image_width));
-            let destination = Rect::new((image_width * offset + start_at) as i32,
-                                        window_height - image_height,
-                                        image_width as u32,
-                                        image_height as u32);
-            ctx.renderer
-                .copy(forest_texture, None, Some(destination))
-                .unwrap();
+    fn animate(&mut self) {
+        if self.sliding.is_some() {
+            self.player_animator.play("slide");
+        } else if self.jumping {
+            if self.velocity_y < 0.00 {
+                self.player_animator.play("jump_up");
+            } else {
+                self.player_animator.play("jump_down");
+            }
+        } else {
+            self.player_animator.play("run");
+        }
+
+        let millis = (config::TICK_MILLIS as f32 * self.step) as u64;
+
+        self.player_animator.advance(millis);
+
+        for obstacle in self.obstacles.iter_mut() {
+            obstacle.animator.advance(millis);
+        }
+
+        for pickup in self.pickups.iter_mut() {
+            pickup.animator.advance(millis);
         }
     }
 
-    fn draw_ground(&self, ctx: &mut Context) {
-        let background_texture = self.textures.get("background".into()).unwrap();
-        let millis = helpers::get_milliseconds(&ctx.timer.elapsed());
-        let window_height = (ctx.renderer.window().unwrap().size().1) as i32;
-        let image_width = (background_texture.query().width * GROUND_SCALE) as i32;
-        let image_height = (background_texture.query().height * GROUND_SCALE) as i32;
-        let x = ((millis % 1500) as i32 * image_width / 1500) as i32;
+    fn player_center(&self) -> (f32, f32) {
+        let (left, top, width, height) = self.character.hitbox;
 
-        for n in 0..3 {
-            let offset = n as i32;
-            let start_at = n + (-(image_width));
-            let destination = Rect::new((image_width * offset + start_at + x) as i32,
-                                        window_height - image_height,
-                                        image_width as u32,
-                                        image_height as u32);
-            ctx.renderer
-                .copy(background_texture, None, Some(destination))
-                .unwrap();
+        (500.0 + left + width / 2.0,
+         self.position_y + self.character.offset_y as f32 + top + height / 2.0)
+    }
+
+    fn update_camera(&mut self, ctx: &mut Context) {
+        if self.dying.is_none() {
+            let (center_x, center_y) = (config::WINDOW_WIDTH as f32 / 2.0,
+                                        config::WINDOW_HEIGHT as f32 / 2.0);
+
+            // Follow a fraction of the jump so the ground stays in view
+            self.camera
+                .follow(center_x, center_y + (self.position_y - 400.00) * CAMERA_FOLLOW);
         }
+
+        self.camera.update(&mut ctx.thread_rng);
+    }
+
+    fn draw_debug(&self, ctx: &mut Context) {
+        if !ctx.debug.enabled {
+            return;
+        }
+
+        for body in &self.collisions.bodies {
+            let color = if body.layer == LAYER_PLAYER {
+                Color::RGBA(0, 255, 0, 255)
+            } else {
+                Color::RGBA(0, 120, 255, 255)
+            };
+
+            ctx.debug
+                .boxes
+                .push((self.camera.transform(body.destination().rect()), color));
+        }
+
+        for contact in &self.contacts {
+            let (x, y) = (contact.overlap.x + contact.overlap.width / 2.0,
+                          contact.overlap.y + contact.overlap.height / 2.0);
+            let start = self.camera.to_screen(x, y);
+            let end = self.camera
+                .to_screen(x + contact.normal.0 * 30.0, y + contact.normal.1 * 30.0);
+
+            ctx.debug
+                .boxes
+                .push((self.camera.transform(contact.overlap.rect()), Color::RGBA(255, 0, 0, 255)));
+            ctx.debug
+                .lines
+                .push((Point::new(start.0, start.1),
+                       Point::new(end.0, end.1),
+                       Color::RGBA(255, 0, 0, 255)));
+            ctx.debug
+                .text
+                .push(format!("contact #{} layer {} at t={:.2}",
+                              contact.other,
+                              contact.layer,
+                              contact.time));
+        }
+
+        ctx.debug
+            .text
+            .push(format!("player y {:.1} vy {:.2} jumping {} sliding {:?} cooldown {:.0}",
+                          self.position_y,
+                          self.velocity_y,
+                          self.jumping,
+                          self.sliding,
+                          self.slide_cooldown));
+        ctx.debug
+            .text
+            .push(format!("lives {}/{} invulnerable {:.0} knockback {:.1}",
+                          self.lives,
+                          self.difficulty.lives,
+                          self.invulnerable,
+                          self.knockback));
+        let progress = self.progress();
+        let (min, max) = self.difficulty.stacks(&progress);
+
+        ctx.debug
+            .text
+            .push(format!("difficulty {} distance {:.0} gap {:.0} stacks {}..{}",
+                          self.difficulty.name,
+                          self.distance,
+                          self.difficulty.gap(&progress),
+                          min,
+                          max));
+        ctx.debug
+            .text
+            .push(format!("chunk {} {} stacks pending",
+                          self.chunk_name,
+                          self.pending.len()));
+        ctx.debug
+            .text
+            .push(format!("world speed {:.2} trauma {:.2} zoom {:.2}",
+                          self.world_speed,
+                          self.camera.trauma,
+                          self.camera.zoom));
+
+        for active in &self.powerups.active {
+            ctx.debug
+                .text
+                .push(format!("power {} {:.0} ticks x{} charges {}",
+                              active.power.name(),
+                              active.remaining,
+                              active.stacks,
+                              active.charges));
+        }
+
+        for obstacle in &self.obstacles {
+            ctx.debug
+                .text
+                .push(format!("obstacle #{} {:?} x {:.1}{}",
+                              obstacle.id,
+                              obstacle.kind,
+                              obstacle.position_x,
+                              if obstacle.pending_point { "" } else { " cleared" }));
+        }
+    }
+
+    fn die(&mut self, contact: Contact, ctx: &mut Context) {
+        let (x, y) = self.player_center();
+        let (hit_x, hit_y) = (contact.overlap.x + contact.overlap.width / 2.0,
+                              contact.overlap.y + contact.overlap.height / 2.0);
+
+        for obstacle in self.obstacles.iter_mut() {
+            if obstacle.id == contact.other {
+                obstacle.pending_point = false;
+                self.stats.cause = obstacle.kind.cause();
+            }
+        }
+
+        self.lives = 0;
+        self.dying = Some(DEATH_TICKS);
+        self.world_speed = 0.0;
+        self.camera.add_trauma(0.8);
+        self.camera.zoom_into(x, y, DEATH_ZOOM);
+        self.particles.emit("debris", hit_x, hit_y, &mut ctx.thread_rng);
+        self.particles.emit("impact", hit_x, hit_y, &mut ctx.thread_rng);
     }
 }
 
@@ -292,59 +1268,196 @@ impl Scene for Game {
     fn new() -> Self {
         Self {
             textures: HashMap::new(),
+            sheets: HashMap::new(),
+            masks: HashMap::new(),
+            player_animator: Animator::new("run"),
+            character: Character::new(),
+            background: Parallax { layers: Vec::new() },
+            camera: Camera::new(),
+            particles: ParticleSystem::new(),
+            dying: None,
+            lives: 1,
+            invulnerable: 0.0,
+            knockback: 0.0,
+            difficulty: Preset::new(difficulty::DEFAULT_PRESET),
+            distance: 0.0,
+            world_speed: 0.0,
+            step: 1.0,
+            movement: Movement::new(GROUND_Y),
+            god: false,
+            rng: rand::weak_rng(),
             velocity_y: 0.00,
             position_y: 400.00,
             jumping: false,
             released: true,
+            airborne: 0.0,
+            air_jumps: 0,
+            buffered: 0.0,
+            falling: false,
+            sliding: None,
+            slide_cooldown: 0.0,
+            fast_falling: false,
             obstacles: Vec::new(),
+            next_obstacle_id: PLAYER_BODY + 1,
+            patterns: Patterns { chunks: Vec::new() },
+            pending: VecDeque::new(),
+            chunk_name: String::new(),
+            chunk_distance: 0.0,
+            chunk_after: 0.0,
+            pickups: Vec::new(),
+            pending_pickups: VecDeque::new(),
+            powerups: PowerUps::new(),
+            modifiers: Modifiers::new(),
+            collisions: CollisionWorld::new(),
+            contacts: Vec::new(),
             points: 0,
+            scoring: Scoring::new(Rules::new()),
+            popups: Vec::new(),
+            stats: RunStats::new(),
+            achievements: Vec::new(),
+            profile: Save::new(),
+            toasts: VecDeque::new(),
+            continues: 0,
+            coins: 0,
+            banked: true,
         }
     }
 
-    fn on_unload(&mut self, _ctx: &mut Context) -> Loop {
+    fn on_unload(&mut self, ctx: &mut Context) -> Loop {
+        self.finish_run(ctx);
         Loop::Continue
     }
 
     fn on_load(&mut self, ctx: &mut Context) -> Loop {
         self.points = 0;
+        self.scoring = Scoring::new(Rules::load(Path::new("./assets/game.scoring")).unwrap());
+        self.popups.clear();
+        self.stats = RunStats::new();
+        self.achievements = achievements::load(Path::new("./assets/game.achievements")).unwrap();
+        self.toasts.clear();
+        self.profile = profiles::load(&ctx.registry).unwrap_or_else(|err| {
+            self.toasts.push_back(Toast::new(err));
+            Save::new()
+        });
+        self.dying = None;
+        self.invulnerable = 0.0;
+        self.knockback = 0.0;
+        self.distance = 0.0;
+        self.camera = Camera::new();
+        self.velocity_y = 0.00;
+        self.position_y = 400.00;
+        self.jumping = false;
+        self.released = true;
+        self.airborne = 0.0;
+        self.buffered = 0.0;
+        self.falling = false;
+        self.sliding = None;
+        self.slide_cooldown = 0.0;
+        self.fast_falling = false;
+        self.obstacles.clear();
+        self.contacts.clear();
+        self.pending.clear();
+        self.chunk_name.clear();
+        self.chunk_distance = 0.0;
+        self.chunk_after = 0.0;
+        self.pickups.clear();
+        self.pending_pickups.clear();
+        self.powerups = PowerUps::load(Path::new("./assets/game.powerups")).unwrap();
+        self.grant_starting_powers(ctx);
+        self.continues = self.profile.stock(shop::CONTINUE);
+        self.modifiers = Modifiers::new();
+        self.coins = 0;
+        self.banked = false;
 
+        // A seed set from the console makes every following run identical
+        self.rng = match ctx.seed {
+            Some(seed) => {
+                XorShiftRng::from_seed([seed as u32, (seed >> 32) as u32, 0x9e37_79b9, 0x7f4a_7c15])
+            }
+            None => ctx.thread_rng.gen(),
+        };
+
+        self.publish_score(ctx);
         ctx.registry
-            .insert("points".into(),
-                    RegistryItem::Number(self.points.clone() as usize));
+            .insert("coins".into(), RegistryItem::Number(0));
+
+        let presets = difficulty::load(Path::new("./assets/difficulty.presets")).unwrap();
+        let selected = match ctx.registry.get("difficulty") {
+            Some(RegistryItem::Text(name)) => name.clone(),
+            _ => difficulty::DEFAULT_PRESET.to_string(),
+        };
+
+        self.difficulty = difficulty::find(&presets, &selected);
+        self.lives = self.difficulty.lives;
+        self.movement = Movement::load(Path::new("./assets/player.movement"), GROUND_Y).unwrap();
+
+        if let Some(air_jumps) = self.difficulty.air_jumps {
+            self.movement.air_jumps = air_jumps;
+        }
+
+        let roster = characters::load(Path::new("./assets/game.characters")).unwrap();
+        let (character, skin) = characters::selected(&roster, &self.profile);
+
+        self.character = character.clone();
+        self.movement.jump.jump_velocity *= character.velocity_scale();
+        self.movement.air_jump_velocity *= character.velocity_scale();
+
+        self.air_jumps = self.movement.air_jumps;
+        self.patterns = Patterns::load(Path::new("./assets/obstacles.chunks")).unwrap();
+        self.world_speed = self.difficulty.speed(&self.progress());
 
         ctx.sounds
             .insert("jump".into(),
                     Chunk::from_file(Path::new("./assets/jump.wav")).unwrap());
+        ctx.sounds
+            .insert("coin".into(),
+                    Chunk::from_file(Path::new("./assets/coin.wav")).unwrap());
 
-        self.textures
-            .insert("background".into(),
-                    ctx.renderer
-                        .load_texture(Path::new("./assets/background.png"))
-                        .unwrap());
+        self.background = Parallax::load(Path::new("./assets/background.layers")).unwrap();
 
-        self.textures
-            .insert("montains".into(),
-                    ctx.renderer
-                        .load_texture(Path::new("./assets/background-montains.png"))
-                        .unwrap());
+        for layer in &self.background.layers {
+            self.textures
+                .insert(layer.texture.clone(),
+                        ctx.renderer
+                            .load_texture(Path::new(&layer.texture))
+                            .unwrap());
+        }
 
-        self.textures
-            .insert("forest".into(),
-                    ctx.renderer
-                        .load_texture(Path::new("./assets/background-forest.png"))
-                        .unwrap());
+        for name in &["player", "rocks", "hazards", "pickups"] {
+            let path = match *name {
+                "player" => self.character.sheet.clone(),
+                _ => format!("./assets/{}.sheet", name),
+            };
+            let sheet = SpriteSheet::load(Path::new(&path)).unwrap();
 
-        self.textures
-            .insert("player".into(),
-                    ctx.renderer
-                        .load_texture(Path::new("./assets/player.png"))
-                        .unwrap());
+            self.textures
+                .insert(sheet.texture.clone(),
+                        ctx.renderer
+                            .load_texture(Path::new(&sheet.texture))
+                            .unwrap());
+
+            self.masks
+                .insert(name.to_string(), MaskSet::from_sheet(&sheet).unwrap());
+            self.sheets.insert(name.to_string(), sheet);
+        }
+
+        // The skin tints the whole sheet, it's only drawn for the player
+        let (red, green, blue) = skin.color;
 
         self.textures
-            .insert("rocks".into(),
-                    ctx.renderer
-                        .load_texture(Path::new("./assets/rocks.png"))
-                        .unwrap());
+            .get_mut(&self.sheets["player"].texture)
+            .unwrap()
+            .set_color_mod(red, green, blue);
+
+        self.particles = ParticleSystem::load(Path::new("./assets/effects.particles")).unwrap();
+
+        for texture in self.particles.textures() {
+            let renderer = &ctx.renderer;
+
+            self.textures
+                .entry(texture.clone())
+                .or_insert_with(|| renderer.load_texture(Path::new(&texture)).unwrap());
+        }
 
         Loop::Continue
     }
@@ -353,21 +1466,34 @@ impl Scene for Game {
         match event {
             Event::Quit { .. } => Loop::Break,
             Event::KeyDown { keycode: Some(Keycode::Space), .. } => {
-                if !self.jumping && self.released {
+                // Key repeats don't count as new presses
+                if self.released {
                     self.released = false;
-                    self.jumping = true;
-                    self.velocity_y = -16.00;
-                    channel(2)
-                        .play(ctx.sounds.get("jump".into()).unwrap(), 0)
-                        .unwrap();
+
+                    if !self.jump(ctx) {
+                        self.buffered = self.movement.buffer as f32;
+                    }
                 }
                 Loop::Continue
             }
             Event::KeyUp { keycode: Some(Keycode::Space), .. } => {
                 self.released = true;
 
-                if self.jumping && self.velocity_y < -6.00 {
-                    self.velocity_y = -6.00;
+                if self.jumping {
+                    self.velocity_y = self.movement.jump.release(self.velocity_y);
+                }
+                Loop::Continue
+            }
+            Event::KeyDown { keycode: Some(Keycode::Down), .. } => {
+                if self.dying.is_some() {
+                    return Loop::Continue;
+                }
+
+                if self.jumping {
+                    self.fast_falling = true;
+                    self.velocity_y = self.velocity_y.max(FAST_FALL);
+                } else {
+                    self.start_slide(ctx);
                 }
                 Loop::Continue
             }
@@ -375,30 +1501,188 @@ impl Scene for Game {
         }
     }
 
-    fn on_tick(&mut self, mut ctx: &mut Context) -> Loop {
+    fn commands(&self) -> Vec<Command> {
+        vec![Command {
+                 usage: "set gravity <value>",
+                 help: "changes the gravity applied every tick",
+             },
+             Command {
+                 usage: "set air_jumps <count>",
+                 help: "changes how many jumps are allowed in the air",
+             },
+             Command {
+                 usage: "god",
+                 help: "toggles collisions with obstacles",
+             },
+             Command {
+                 usage: "spawn rock <count>",
+                 help: "spawns a stack of rocks right away",
+             },
+             Command {
+                 usage: "set lives <count>",
+                 help: "changes the lives left in this run",
+             },
+             Command {
+                 usage: "power <name>",
+                 help: "grants a power as if it was picked up",
+             },
+             Command {
+                 usage: "spawn bird|pit|log|spikes <value>",
+                 help: "spawns another obstacle, with the value its chunk line takes",
+             }]
+    }
+
+    fn on_command(&mut self, args: &[&str], _ctx: &mut Context) -> Option<Result<String, String>> {
+        let result = match *args {
+            ["set", "gravity", value] => {
+                value
+                    .parse::<f32>()
+                    .map(|gravity| {
+                             self.movement.jump.gravity = gravity;
+                             format!("gravity set to {}", gravity)
+                         })
+                    .map_err(|_| format!("invalid gravity `{}`", value))
+            }
+            ["set", "air_jumps", value] => {
+                value
+                    .parse::<u32>()
+                    .map(|count| {
+                             self.movement.air_jumps = count;
+                             self.air_jumps = count;
+                             format!("air jumps set to {}", count)
+                         })
+                    .map_err(|_| format!("invalid air jump count `{}`", value))
+            }
+            ["god"] => {
+                self.god = !self.god;
+                Ok(format!("god mode {}", if self.god { "on" } else { "off" }))
+            }
+            ["spawn", "rock", count] => {
+                match count.parse::<u32>() {
+                    Ok(rocks) if (1..=MAX_ROCKS).contains(&rocks) => {
+                        self.spawn_obstacle(Kind::Rocks(rocks), SPAWN_X);
+                        Ok(format!("spawned {} rocks", rocks))
+                    }
+                    _ => Err(format!("rock count must be between 1 and {}", MAX_ROCKS)),
+                }
+            }
+            ["set", "lives", value] => {
+                match value.parse::<u32>() {
+                    Ok(lives) if lives > 0 => {
+                        self.lives = lives;
+                        Ok(format!("lives set to {}", lives))
+                    }
+                    _ => Err(format!("invalid lives `{}`", value)),
+                }
+            }
+            ["power", name] => {
+                match Power::parse(name) {
+                    Some(power) => {
+                        self.powerups.grant(power);
+                        Ok(format!("granted {}", power.name()))
+                    }
+                    None => Err(format!("unknown power `{}`", name)),
+                }
+            }
+            ["spawn", kind, value] => {
+                let kind = match (kind, value.parse::<f32>()) {
+                    ("bird", Ok(altitude)) => Ok(Kind::Bird(altitude)),
+                    ("pit", Ok(width)) => Ok(Kind::Pit(width)),
+                    ("log", Ok(speed)) => Ok(Kind::Log(speed)),
+                    ("spikes", Ok(count)) if count >= 1.0 => Ok(Kind::Spikes(count as u32)),
+                    ("bird", _) | ("pit", _) | ("log", _) | ("spikes", _) => {
+                        Err(format!("invalid {} value `{}`", kind, value))
+                    }
+                    _ => Err(format!("unknown obstacle `{}`", kind)),
+                };
+
+                kind.map(|kind| {
+                             self.spawn_obstacle(kind, SPAWN_X);
+                             format!("spawned {:?}", kind)
+                         })
+            }
+            _ => return None,
+        };
+
+        Some(result)
+    }
+
+    fn on_tick(&mut self, ctx: &mut Context) -> Loop {
         ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
         ctx.renderer.clear();
 
-        self.add_obstacle(&mut ctx);
-        self.physics(&mut ctx);
-        self.draw_montains(&mut ctx);
-        self.draw_forest(&mut ctx);
-        self.draw_ground(&mut ctx);
-        self.draw_obstacles(&mut ctx);
-        self.draw_points(&mut ctx);
+        self.step = ctx.time_scale;
 
-        if self.is_colliding(&mut ctx) {
-            return Loop::GoToScene("game_over".into());
+        if self.dying.is_none() {
+            for power in self.powerups.update(self.step) {
+                self.power_expired(power, ctx);
+            }
+
+            self.modifiers = self.powerups.modifiers();
+            self.stats.add(Stat::Duration, 1);
+            self.invulnerable = (self.invulnerable - self.step).max(0.0);
+            self.knockback *= KNOCKBACK_DECAY.powf(self.step);
+
+            if self.knockback < 0.1 {
+                self.knockback = 0.0;
+            }
         }
 
-        if self.jumping {
-            self.draw_jump(&mut ctx);
-        } else {
-            self.draw_player(&mut ctx);
+        if self.dying.is_none() {
+            self.world_speed = self.difficulty.speed(&self.progress());
+            self.add_obstacle();
         }
 
-        ctx.renderer.present();
+        self.physics(ctx);
+        self.animate();
 
-        Loop::Continue
+        if self.dying.is_none() {
+            self.collect_pickups(ctx);
+            self.update_stats();
+            self.check_achievements();
+        }
+
+        if self.dying.is_none() && self.is_colliding(ctx) && !self.god &&
+           self.invulnerable <= 0.0 {
+            let contact = self.contacts[0];
+
+            if self.powerups.absorb_hit().is_some() {
+                self.absorb(contact, ctx);
+            } else if self.lives > 1 {
+                self.hurt(contact, ctx);
+            } else if self.continues > 0 {
+                self.use_continue(contact, ctx);
+            } else {
+                self.die(contact, ctx);
+            }
+        }
+
+        self.update_camera(ctx);
+        self.particles.update(self.world_delta(), self.step, &mut ctx.thread_rng);
+
+        self.background.draw(&mut ctx.renderer, &self.camera, &self.textures);
+        self.draw_obstacles(ctx);
+        self.draw_pickups(ctx);
+        self.draw_player(ctx);
+        self.particles
+            .draw(&mut ctx.renderer, &self.camera, &mut self.textures);
+        self.draw_popups(ctx);
+        self.draw_points(ctx);
+        self.draw_powers(ctx);
+        self.draw_lives(ctx);
+        self.draw_toasts(ctx);
+        self.draw_debug(ctx);
+
+        match self.dying {
+            Some(0) => {
+                Loop::TransitionTo("game_over".into(),
+                                   Transition::Fade(Color::RGB(0, 0, 0), 800))
+            }
+            Some(ticks) => {
+                self.dying = Some(ticks - 1);
+                Loop::Continue
+            }
+            None => Loop::Continue,
+        }
     }
 }
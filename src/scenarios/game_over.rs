@@ -10,23 +10,37 @@ use self::sdl2::render::Texture;
 use self::sdl2::mixer::channel;
 
 use helpers;
-use engine::{Scene, Loop, Context};
+use engine::{Scene, Loop, Context, RegistryItem};
+use scoring::Category;
+use stats::{self, Stat};
+
+/// Vertical offset of the exit button, below the stats.
+const EXIT_Y: i32 = 240;
 
 pub struct GameOver {
     textures: HashMap<String, Texture>,
     over_exit: bool,
+    /// Where the points came from, one category after the other.
+    breakdown: String,
+    /// One line per stat of the run, then how it ended.
+    stats: Vec<String>,
 }
 
 impl<'a> Scene for GameOver {
     fn new() -> Self {
-        Self { textures: HashMap::new(), over_exit: false }
+        Self {
+            textures: HashMap::new(),
+            over_exit: false,
+            breakdown: String::new(),
+            stats: Vec::new(),
+        }
     }
 
     fn on_event(&mut self, event: Event, ctx: &mut Context) -> Loop {
         match event {
             Event::Quit { .. } => Loop::Break,
             Event::MouseMotion { x, y, .. } => {
-                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, 100)) {
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, EXIT_Y)) {
                     if !self.over_exit {
                         channel(1)
                             .play(ctx.sounds.get("menu").unwrap(), 0)
@@ -40,7 +54,7 @@ impl<'a> Scene for GameOver {
                 Loop::Continue
             },
             Event::MouseButtonUp { x, y, .. } => {
-                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, 100)) {
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, EXIT_Y)) {
                     Loop::Break
                 } else {
                     Loop::Continue
@@ -101,6 +115,95 @@ impl<'a> Scene for GameOver {
                         .create_texture_from_surface(&points_surface)
                         .unwrap());
 
+        self.breakdown = Category::ALL
+            .iter()
+            .map(|category| {
+                let points = match ctx.registry.get(&format!("score_{}", category.name())) {
+                    Some(RegistryItem::Number(points)) => *points,
+                    _ => 0,
+                };
+
+                format!("{} {}", category.label(), points)
+            })
+            .collect::<Vec<String>>()
+            .join("   ");
+
+        let breakdown_surface =
+            font.render(&self.breakdown)
+            .blended(Color::RGBA(180, 180, 180, 255))
+            .unwrap();
+
+        self.textures
+            .insert("breakdown".into(),
+                    ctx.renderer
+                        .create_texture_from_surface(&breakdown_surface)
+                        .unwrap());
+
+        self.stats.clear();
+
+        for (index, stat) in Stat::ALL.iter().enumerate() {
+            let value = stats::number(&ctx.registry, &format!("stat_{}", stat.name()));
+            let best = stats::number(&ctx.registry, &format!("best_{}", stat.name()));
+            let new_best = value > best;
+
+            let line = if new_best {
+                format!("{}: {}   NEW BEST", stat.label(), stat.format(value))
+            } else {
+                format!("{}: {}   best {}", stat.label(), stat.format(value), stat.format(best))
+            };
+
+            let color = if new_best {
+                Color::RGBA(255, 210, 0, 255)
+            } else {
+                Color::RGBA(255, 255, 255, 255)
+            };
+
+            let surface = font.render(&line).blended(color).unwrap();
+
+            self.textures
+                .insert(format!("stat_{}", index),
+                        ctx.renderer
+                            .create_texture_from_surface(&surface)
+                            .unwrap());
+            self.stats.push(line);
+        }
+
+        let by_size = stats::text(&ctx.registry, "stat_cleared_by_size");
+
+        if !by_size.is_empty() {
+            self.stats.push(format!("Cleared {}", by_size));
+        }
+
+        let cause = stats::text(&ctx.registry, "stat_cause");
+
+        if !cause.is_empty() {
+            self.stats.push(cause);
+        }
+
+        let save_error = stats::text(&ctx.registry, "save_error");
+        let failed = !save_error.is_empty();
+
+        if failed {
+            self.stats.push(save_error);
+        }
+
+        for index in Stat::ALL.len()..self.stats.len() {
+            let color = if failed && index == self.stats.len() - 1 {
+                Color::RGBA(255, 90, 90, 255)
+            } else {
+                Color::RGBA(180, 180, 180, 255)
+            };
+            let surface = font.render(&self.stats[index])
+                .blended(color)
+                .unwrap();
+
+            self.textures
+                .insert(format!("stat_{}", index),
+                        ctx.renderer
+                            .create_texture_from_surface(&surface)
+                            .unwrap());
+        }
+
         let exit_surface =
             font.render("EXIT")
             .blended(Color::RGBA(255, 255, 255, 255))
@@ -122,7 +225,7 @@ impl<'a> Scene for GameOver {
         // Renders the title
         {
             let mut game_over = self.textures.get_mut("game_over").unwrap();
-            let position = helpers::rect_centered(400, 50, 0, -200);
+            let position = helpers::rect_centered(400, 50, 0, -250);
 
             ctx.renderer
                 .copy(&mut game_over, None, Some(position))
@@ -132,7 +235,7 @@ impl<'a> Scene for GameOver {
         // Renders the subtitle
         {
             let mut subtitle = self.textures.get_mut("subtitle").unwrap();
-            let position = helpers::rect_centered(560, 30, 0, -140);
+            let position = helpers::rect_centered(560, 24, 0, -205);
 
             ctx.renderer
                 .copy(&mut subtitle, None, Some(position))
@@ -142,7 +245,7 @@ impl<'a> Scene for GameOver {
         // Renders the points_text
         {
             let mut text = self.textures.get_mut("points_text").unwrap();
-            let position = helpers::rect_centered(430, 30, 0, -100);
+            let position = helpers::rect_centered(430, 24, 0, -175);
 
             ctx.renderer
                 .copy(&mut text, None, Some(position))
@@ -153,14 +256,36 @@ impl<'a> Scene for GameOver {
         {
             let mut text = self.textures.get_mut("points").unwrap();
             let points = ctx.registry.get("points".into()).unwrap().to_string();
-            let width = points.len() * 33;
-            let position = helpers::rect_centered(width as i32, 60, 0, 0);
+            let width = points.len() * 28;
+            let position = helpers::rect_centered(width as i32, 50, 0, -130);
 
             ctx.renderer
                 .copy(&mut text, None, Some(position))
                 .unwrap();
         }
 
+        // Renders the points of every category
+        {
+            let text = self.textures.get("breakdown").unwrap();
+            let width = self.breakdown.len() * 9;
+            let position = helpers::rect_centered(width as i32, 18, 0, -95);
+
+            ctx.renderer
+                .copy(text, None, Some(position))
+                .unwrap();
+        }
+
+        // Renders the stats of the run, under each other
+        for (index, line) in self.stats.iter().enumerate() {
+            let text = self.textures.get(&format!("stat_{}", index)).unwrap();
+            let width = line.len() * 9;
+            let position = helpers::rect_centered(width as i32, 18, 0, -65 + 22 * index as i32);
+
+            ctx.renderer
+                .copy(text, None, Some(position))
+                .unwrap();
+        }
+
         // Renders the exit button
         {
             match self.over_exit {
@@ -169,23 +294,22 @@ impl<'a> Scene for GameOver {
             }
 
             ctx.renderer
-                .fill_rect(helpers::rect_centered(200, 60, 0, 100))
+                .fill_rect(helpers::rect_centered(200, 60, 0, EXIT_Y))
                 .unwrap();
 
             ctx.renderer.set_draw_color(Color::RGB(0, 0, 0));
             ctx.renderer
-                .fill_rect(helpers::rect_centered(190, 50, 0, 100))
+                .fill_rect(helpers::rect_centered(190, 50, 0, EXIT_Y))
                 .unwrap();
 
             let mut exit = self.textures.get_mut("exit").unwrap();
-            let exit_position = helpers::rect_centered(100, 20, 0, 100);
+            let exit_position = helpers::rect_centered(100, 20, 0, EXIT_Y);
 
             ctx.renderer
                 .copy(&mut exit, None, Some(exit_position))
                 .unwrap();
         }
 
-        ctx.renderer.present();
         Loop::Continue
     }
 }
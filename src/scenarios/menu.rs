@@ -5,18 +5,69 @@ use std::collections::HashMap;
 
 use self::sdl2::pixels::Color;
 use self::sdl2::event::Event;
+use self::sdl2::keyboard::Keycode;
 use self::sdl2::ttf;
 use self::sdl2::render::Texture;
 use self::sdl2::mixer::{Chunk, channel};
 
 use helpers;
-use engine::{Scene, Loop, Context};
+use engine::{Scene, Loop, Context, RegistryItem};
+use console::Command;
+use difficulty;
+use profiles;
+use save::Save;
+use transitions::Transition;
 use config;
 
 pub struct Menu {
     textures: HashMap<String, Texture>,
     over_play: bool,
     over_exit: bool,
+    over_difficulty: bool,
+    over_achievements: bool,
+    over_profile: bool,
+    over_character: bool,
+    over_shop: bool,
+    /// Name of the current profile, shown in the corner.
+    profile: String,
+    difficulties: Vec<String>,
+    difficulty: usize,
+    /// Why the profile couldn't be read or saved.
+    status: Option<String>,
+}
+
+impl Menu {
+    /// Moves the difficulty selector and stores the choice for the game and
+    /// in the profile.
+    fn select_difficulty(&mut self, offset: isize, ctx: &mut Context) {
+        let count = self.difficulties.len() as isize;
+        self.difficulty = ((self.difficulty as isize + offset + count) % count) as usize;
+
+        let name = &self.difficulties[self.difficulty];
+
+        ctx.registry
+            .insert("difficulty".into(), RegistryItem::Text(name.clone()));
+
+        if let Err(err) = Menu::save_setting(&self.profile, "difficulty", name) {
+            self.status = Some(format!("Could not save the difficulty: {}", err));
+        }
+    }
+
+    /// Stores a setting in a profile's save, writing nothing when it already
+    /// has that value.
+    fn save_setting(profile: &str, key: &str, value: &str) -> Result<(), String> {
+        let path = profiles::path(profile);
+
+        if Save::load(&path)?.setting(key) == Some(value) {
+            return Ok(());
+        }
+
+        Save::transact(&path, |save| {
+                save.settings.insert(key.into(), value.into());
+                Ok(())
+            })
+            .map(|_| ())
+    }
 }
 
 impl<'a> Scene for Menu {
@@ -25,6 +76,15 @@ impl<'a> Scene for Menu {
             textures: HashMap::new(),
             over_play: false,
             over_exit: false,
+            over_difficulty: false,
+            over_achievements: false,
+            over_profile: false,
+            over_character: false,
+            over_shop: false,
+            profile: String::new(),
+            difficulties: Vec::new(),
+            difficulty: 0,
+            status: None,
         }
     }
 
@@ -87,9 +147,109 @@ impl<'a> Scene for Menu {
 
         self.textures.insert("exit".into(), exit_texture);
 
+        let achievements_surface = font.render("ACHIEVEMENTS")
+            .blended(Color::RGBA(0, 0, 0, 255))
+            .unwrap();
+
+        let achievements_texture = ctx.renderer
+            .create_texture_from_surface(&achievements_surface)
+            .unwrap();
+
+        self.textures
+            .insert("achievements".into(), achievements_texture);
+
+        self.status = None;
+
+        if let Err(err) = profiles::ensure() {
+            self.status = Some(format!("Could not set up profiles: {}", err));
+        }
+
+        self.profile = profiles::current(&ctx.registry);
+
+        if let Err(err) = profiles::select(&self.profile, &mut ctx.registry) {
+            self.status = Some(err);
+        }
+
+        let profile = match profiles::load(&ctx.registry) {
+            Ok(profile) => {
+                if let Some(ref err) = profile.restored {
+                    self.status = Some(format!("{}, using its backup", err));
+                }
+
+                profile
+            }
+            Err(err) => {
+                self.status = Some(err);
+                Save::new()
+            }
+        };
+        let profile_surface = font.render(&self.profile.to_uppercase())
+            .blended(Color::RGBA(0, 0, 0, 255))
+            .unwrap();
+
+        let profile_texture = ctx.renderer
+            .create_texture_from_surface(&profile_surface)
+            .unwrap();
+
+        self.textures.insert("profile".into(), profile_texture);
+
+        let character_surface = font.render("CHARACTER")
+            .blended(Color::RGBA(0, 0, 0, 255))
+            .unwrap();
+
+        let character_texture = ctx.renderer
+            .create_texture_from_surface(&character_surface)
+            .unwrap();
+
+        self.textures.insert("character".into(), character_texture);
+
+        let shop_surface = font.render("SHOP")
+            .blended(Color::RGBA(0, 0, 0, 255))
+            .unwrap();
+
+        let shop_texture = ctx.renderer
+            .create_texture_from_surface(&shop_surface)
+            .unwrap();
+
+        self.textures.insert("shop".into(), shop_texture);
+
+        self.difficulties = difficulty::load(Path::new("./assets/difficulty.presets"))
+            .unwrap()
+            .into_iter()
+            .map(|preset| preset.name)
+            .collect();
+
+        for name in &self.difficulties {
+            let surface = font.render(&name.to_uppercase())
+                .blended(Color::RGBA(0, 0, 0, 255))
+                .unwrap();
+
+            let texture = ctx.renderer
+                .create_texture_from_surface(&surface)
+                .unwrap();
+
+            self.textures.insert(format!("difficulty_{}", name), texture);
+        }
+
+        // The profile's choice, else the last one when coming back from a run
+        let selected = match (profile.setting("difficulty"), ctx.registry.get("difficulty")) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(RegistryItem::Text(name))) => name.clone(),
+            _ => difficulty::DEFAULT_PRESET.to_string(),
+        };
+
+        self.difficulty = self.difficulties
+            .iter()
+            .position(|name| *name == selected)
+            .unwrap_or(0);
+        self.select_difficulty(0, ctx);
+
         let channel = channel(0);
 
-        channel.set_volume(80);
+        channel.set_volume(profile
+                               .setting("volume")
+                               .and_then(|volume| volume.parse().ok())
+                               .unwrap_or(80));
         channel
             .play(ctx.sounds.get("music").unwrap(), -1)
             .unwrap();
@@ -127,6 +287,66 @@ impl<'a> Scene for Menu {
                     self.over_exit = false;
                 }
 
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, 170)) {
+                    if !self.over_difficulty {
+                        channel(1)
+                            .play(ctx.sounds.get("menu").unwrap(), 0)
+                            .unwrap();
+                    }
+
+                    self.over_difficulty = true;
+                } else {
+                    self.over_difficulty = false;
+                }
+
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 60, 0, 240)) {
+                    if !self.over_achievements {
+                        channel(1)
+                            .play(ctx.sounds.get("menu").unwrap(), 0)
+                            .unwrap();
+                    }
+
+                    self.over_achievements = true;
+                } else {
+                    self.over_achievements = false;
+                }
+
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 40, 290, -270)) {
+                    if !self.over_profile {
+                        channel(1)
+                            .play(ctx.sounds.get("menu").unwrap(), 0)
+                            .unwrap();
+                    }
+
+                    self.over_profile = true;
+                } else {
+                    self.over_profile = false;
+                }
+
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 40, -290, -270)) {
+                    if !self.over_character {
+                        channel(1)
+                            .play(ctx.sounds.get("menu").unwrap(), 0)
+                            .unwrap();
+                    }
+
+                    self.over_character = true;
+                } else {
+                    self.over_character = false;
+                }
+
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 40, 290, 270)) {
+                    if !self.over_shop {
+                        channel(1)
+                            .play(ctx.sounds.get("menu").unwrap(), 0)
+                            .unwrap();
+                    }
+
+                    self.over_shop = true;
+                } else {
+                    self.over_shop = false;
+                }
+
                 Loop::Continue
             }
             Event::MouseButtonUp { x, y, .. } => {
@@ -136,11 +356,40 @@ impl<'a> Scene for Menu {
                                                         y,
                                                         &helpers::rect_centered(200, 60, 0, 30)) {
 
-                    Loop::GoToScene("game".into())
+                    Loop::TransitionTo("game".into(), Transition::CircleWipe(700))
+                } else if helpers::point_colliding_rect(x,
+                                                        y,
+                                                        &helpers::rect_centered(200, 60, 0, 170)) {
+                    self.select_difficulty(1, ctx);
+                    Loop::Continue
+                } else if helpers::point_colliding_rect(x,
+                                                        y,
+                                                        &helpers::rect_centered(200, 60, 0, 240)) {
+                    Loop::GoToScene("achievements".into())
+                } else if helpers::point_colliding_rect(x,
+                                                        y,
+                                                        &helpers::rect_centered(200, 40, 290, -270)) {
+                    Loop::GoToScene("profiles".into())
+                } else if helpers::point_colliding_rect(x,
+                                                        y,
+                                                        &helpers::rect_centered(200, 40, -290, -270)) {
+                    Loop::GoToScene("characters".into())
+                } else if helpers::point_colliding_rect(x,
+                                                        y,
+                                                        &helpers::rect_centered(200, 40, 290, 270)) {
+                    Loop::GoToScene("shop".into())
                 } else {
                     Loop::Continue
                 }
             }
+            Event::KeyDown { keycode: Some(Keycode::Left), .. } => {
+                self.select_difficulty(-1, ctx);
+                Loop::Continue
+            }
+            Event::KeyDown { keycode: Some(Keycode::Right), .. } => {
+                self.select_difficulty(1, ctx);
+                Loop::Continue
+            }
             _ => Loop::Continue,
         }
     }
@@ -217,7 +466,159 @@ impl<'a> Scene for Menu {
                 .unwrap();
         }
 
-        ctx.renderer.present();
+        // Renders the difficulty selector, clicking it cycles the presets
+        {
+            match self.over_difficulty {
+                true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+                false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+            }
+
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(200, 60, 0, 170))
+                .unwrap();
+
+            ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(190, 50, 0, 170))
+                .unwrap();
+
+            // Same width per letter as the PLAY and EXIT labels
+            let name = &self.difficulties[self.difficulty];
+            let difficulty = self.textures.get(&format!("difficulty_{}", name)).unwrap();
+            let difficulty_position = helpers::rect_centered(25 * name.len() as i32, 20, 0, 170);
+
+            ctx.renderer
+                .copy(difficulty, None, Some(difficulty_position))
+                .unwrap();
+        }
+
+        // Renders the achievements button
+        {
+            match self.over_achievements {
+                true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+                false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+            }
+
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(200, 60, 0, 240))
+                .unwrap();
+
+            ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(190, 50, 0, 240))
+                .unwrap();
+
+            // Narrower letters than the other labels to fit the button
+            let achievements = self.textures.get("achievements").unwrap();
+            let achievements_position = helpers::rect_centered(168, 20, 0, 240);
+
+            ctx.renderer
+                .copy(achievements, None, Some(achievements_position))
+                .unwrap();
+        }
+
+        // Renders the current profile in the corner, clicking it manages them
+        {
+            match self.over_profile {
+                true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+                false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+            }
+
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(200, 40, 290, -270))
+                .unwrap();
+
+            ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(190, 30, 290, -270))
+                .unwrap();
+
+            let profile = self.textures.get("profile").unwrap();
+            let profile_position =
+                helpers::rect_centered(10 * self.profile.len() as i32, 16, 290, -270);
+
+            ctx.renderer
+                .copy(profile, None, Some(profile_position))
+                .unwrap();
+        }
+
+        // Renders the character button in the other corner
+        {
+            match self.over_character {
+                true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+                false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+            }
+
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(200, 40, -290, -270))
+                .unwrap();
+
+            ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(190, 30, -290, -270))
+                .unwrap();
+
+            let character = self.textures.get("character").unwrap();
+            let character_position = helpers::rect_centered(90, 16, -290, -270);
+
+            ctx.renderer
+                .copy(character, None, Some(character_position))
+                .unwrap();
+        }
+
+        // Renders the shop button in the bottom corner
+        {
+            match self.over_shop {
+                true => ctx.renderer.set_draw_color(Color::RGB(255, 0, 0)),
+                false => ctx.renderer.set_draw_color(Color::RGB(0, 0, 0)),
+            }
+
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(200, 40, 290, 270))
+                .unwrap();
+
+            ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+            ctx.renderer
+                .fill_rect(helpers::rect_centered(190, 30, 290, 270))
+                .unwrap();
+
+            let shop = self.textures.get("shop").unwrap();
+            let shop_position = helpers::rect_centered(40, 16, 290, 270);
+
+            ctx.renderer
+                .copy(shop, None, Some(shop_position))
+                .unwrap();
+        }
+
+        if let Some(ref status) = self.status {
+            helpers::draw_text(status, Color::RGBA(200, 0, 0, 255), (0, -25), ctx);
+        }
+
         Loop::Continue
     }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![Command {
+                 usage: "volume <0-128>",
+                 help: "sets the music volume of the profile",
+             }]
+    }
+
+    fn on_command(&mut self, args: &[&str], _ctx: &mut Context) -> Option<Result<String, String>> {
+        let result = match *args {
+            ["volume", value] => {
+                match value.parse::<i32>() {
+                    Ok(volume) if (0..=128).contains(&volume) => {
+                        channel(0).set_volume(volume);
+                        Menu::save_setting(&self.profile, "volume", &volume.to_string())
+                            .map(|_| format!("volume set to {}", volume))
+                    }
+                    _ => Err(format!("invalid volume `{}`", value)),
+                }
+            }
+            _ => return None,
+        };
+
+        Some(result)
+    }
 }
@@ -1,3 +1,7 @@
 pub mod menu;
 pub mod game;
 pub mod game_over;
+pub mod achievements;
+pub mod profiles;
+pub mod characters;
+pub mod shop;
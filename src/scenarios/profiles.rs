@@ -0,0 +1,294 @@
+extern crate sdl2;
+
+use std::path::Path;
+use std::collections::HashMap;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::pixels::Color;
+use self::sdl2::event::Event;
+use self::sdl2::keyboard::Keycode;
+use self::sdl2::ttf;
+use self::sdl2::render::Texture;
+use self::sdl2::mixer::channel;
+
+use helpers;
+use profiles;
+use engine::{Scene, Loop, Context};
+
+const BUTTONS_Y: i32 = 220;
+/// Buttons under the list, by texture name and horizontal offset.
+const BUTTONS: [(&str, i32); 4] = [("new", -225), ("rename", -75), ("delete", 75), ("back", 225)];
+
+/// Lists the profiles, picking one by clicking it, and creates, renames
+/// and deletes them with the name typed in.
+pub struct Profiles {
+    textures: HashMap<String, Texture>,
+    names: Vec<String>,
+    current: String,
+    /// Name typed for creating or renaming.
+    input: String,
+    /// Outcome of the last operation, and whether it failed.
+    status: Option<(String, bool)>,
+    /// Set by a first click on delete, the second one deletes.
+    confirm_delete: bool,
+    /// Button under the mouse.
+    over: Option<usize>,
+}
+
+impl Profiles {
+    fn row(index: usize) -> Rect {
+        helpers::rect_centered(300, 28, 0, -190 + 32 * index as i32)
+    }
+
+    fn button(index: usize) -> Rect {
+        helpers::rect_centered(140, 50, BUTTONS[index].1, BUTTONS_Y)
+    }
+
+    /// Reads the profiles again and renders their names.
+    fn refresh(&mut self, ctx: &mut Context) {
+        self.names = profiles::list().unwrap_or_else(|err| {
+            self.status = Some((format!("could not list profiles: {}", err), true));
+            Vec::new()
+        });
+        self.current = profiles::current(&ctx.registry);
+
+        let font = ctx.fonts.get_mut("default").unwrap();
+
+        for name in &self.names {
+            let surface = font.render(name)
+                .blended(Color::RGBA(0, 0, 0, 255))
+                .unwrap();
+
+            self.textures
+                .insert(format!("profile_{}", name),
+                        ctx.renderer
+                            .create_texture_from_surface(&surface)
+                            .unwrap());
+        }
+    }
+
+    fn report(&mut self, result: Result<String, String>) {
+        self.status = Some(match result {
+                               Ok(message) => (message, false),
+                               Err(err) => (err, true),
+                           });
+    }
+
+    fn press(&mut self, button: &str, ctx: &mut Context) -> Loop {
+        let confirm_delete = self.confirm_delete;
+        self.confirm_delete = false;
+
+        match button {
+            "new" => {
+                let result = profiles::create(&self.input).and_then(|_| {
+                    profiles::select(&self.input, &mut ctx.registry)?;
+                    Ok(format!("Created `{}`", self.input))
+                });
+
+                self.finish(result, ctx);
+            }
+            "rename" => {
+                let current = self.current.clone();
+                let result = profiles::rename(&current, &self.input).and_then(|_| {
+                    profiles::select(&self.input, &mut ctx.registry)?;
+                    Ok(format!("Renamed `{}` to `{}`", current, self.input))
+                });
+
+                self.finish(result, ctx);
+            }
+            "delete" if !confirm_delete => {
+                self.confirm_delete = true;
+                self.status = Some((format!("Click DELETE again to delete `{}`", self.current),
+                                    true));
+            }
+            "delete" => {
+                let current = self.current.clone();
+                let result = profiles::delete(&current).and_then(|_| {
+                    // The profile left is picked again from the files
+                    ctx.registry.remove("profile");
+                    let next = profiles::current(&ctx.registry);
+                    profiles::select(&next, &mut ctx.registry)?;
+                    Ok(format!("Deleted `{}`", current))
+                });
+
+                self.finish(result, ctx);
+            }
+            _ => return Loop::GoToScene("menu".into()),
+        }
+
+        Loop::Continue
+    }
+
+    /// Reports an operation. A profile may have changed even when it failed
+    /// to be remembered, so the list is always read again.
+    fn finish(&mut self, result: Result<String, String>, ctx: &mut Context) {
+        if result.is_ok() {
+            self.input.clear();
+        }
+
+        self.refresh(ctx);
+        self.report(result);
+    }
+}
+
+impl Scene for Profiles {
+    fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+            names: Vec::new(),
+            current: String::new(),
+            input: String::new(),
+            status: None,
+            confirm_delete: false,
+            over: None,
+        }
+    }
+
+    fn on_event(&mut self, event: Event, ctx: &mut Context) -> Loop {
+        match event {
+            Event::Quit { .. } => Loop::Break,
+            Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                Loop::GoToScene("menu".into())
+            }
+            Event::KeyDown { keycode: Some(Keycode::Return), .. } => self.press("new", ctx),
+            Event::KeyDown { keycode: Some(Keycode::Backspace), .. } => {
+                self.input.pop();
+                Loop::Continue
+            }
+            Event::TextInput { ref text, .. } => {
+                self.input.push_str(text);
+                Loop::Continue
+            }
+            Event::MouseMotion { x, y, .. } => {
+                let over = (0..BUTTONS.len())
+                    .find(|index| helpers::point_colliding_rect(x, y, &Profiles::button(*index)));
+
+                if over.is_some() && over != self.over {
+                    channel(1)
+                        .play(ctx.sounds.get("menu").unwrap(), 0)
+                        .unwrap();
+                }
+
+                self.over = over;
+                Loop::Continue
+            }
+            Event::MouseButtonUp { x, y, .. } => {
+                if let Some(index) = (0..BUTTONS.len())
+                       .find(|index| helpers::point_colliding_rect(x, y, &Profiles::button(*index))) {
+                    return self.press(BUTTONS[index].0, ctx);
+                }
+
+                if let Some(index) = (0..self.names.len())
+                       .find(|index| helpers::point_colliding_rect(x, y, &Profiles::row(*index))) {
+                    let name = self.names[index].clone();
+
+                    self.status = profiles::select(&name, &mut ctx.registry)
+                        .err()
+                        .map(|err| (err, true));
+                    ctx.registry.remove("difficulty");
+                    self.current = name;
+                    self.confirm_delete = false;
+                }
+
+                Loop::Continue
+            }
+            _ => Loop::Continue,
+        }
+    }
+
+    fn on_load(&mut self, ctx: &mut Context) -> Loop {
+        let mut font = ctx.ttf_context
+            .load_font(Path::new("./assets/font.ttf"), 128)
+            .unwrap();
+
+        font.set_style(ttf::STYLE_BOLD);
+
+        for &(name, _) in BUTTONS.iter() {
+            let surface = font.render(&name.to_uppercase())
+                .blended(Color::RGBA(0, 0, 0, 255))
+                .unwrap();
+
+            self.textures
+                .insert(name.into(),
+                        ctx.renderer
+                            .create_texture_from_surface(&surface)
+                            .unwrap());
+        }
+
+        let title_surface = font.render("Profiles")
+            .blended(Color::RGBA(0, 0, 0, 255))
+            .unwrap();
+
+        self.textures
+            .insert("title".into(),
+                    ctx.renderer
+                        .create_texture_from_surface(&title_surface)
+                        .unwrap());
+
+        self.input.clear();
+        self.status = None;
+        self.confirm_delete = false;
+        self.refresh(ctx);
+
+        Loop::Continue
+    }
+
+    fn on_tick(&mut self, ctx: &mut Context) -> Loop {
+        ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+        ctx.renderer.clear();
+
+        // Renders the title
+        {
+            let title = self.textures.get("title").unwrap();
+
+            ctx.renderer
+                .copy(title, None, Some(helpers::rect_centered(240, 40, 0, -250)))
+                .unwrap();
+        }
+
+        // Renders every profile, the current one framed
+        for (index, name) in self.names.iter().enumerate() {
+            let row = Profiles::row(index);
+
+            if *name == self.current {
+                ctx.renderer.set_draw_color(Color::RGB(0, 0, 0));
+                ctx.renderer.draw_rect(row).unwrap();
+            }
+
+            let text = self.textures.get(&format!("profile_{}", name)).unwrap();
+            let width = 10 * name.len() as i32;
+            let position = helpers::rect_centered(width, 16, 0, -190 + 32 * index as i32);
+
+            ctx.renderer
+                .copy(text, None, Some(position))
+                .unwrap();
+        }
+
+        helpers::draw_text(&format!("Name: {}_", self.input),
+                           Color::RGBA(0, 0, 0, 255),
+                           (0, 120),
+                           ctx);
+
+        if let Some((ref message, failed)) = self.status {
+            let color = if failed {
+                Color::RGBA(200, 0, 0, 255)
+            } else {
+                Color::RGBA(0, 140, 0, 255)
+            };
+
+            helpers::draw_text(message, color, (0, 155), ctx);
+        }
+
+        // Renders the buttons
+        for (index, &(name, _)) in BUTTONS.iter().enumerate() {
+            helpers::draw_button(Profiles::button(index),
+                                 self.textures.get(name).unwrap(),
+                                 (16 * name.len() as u32, 18),
+                                 self.over == Some(index),
+                                 ctx);
+        }
+
+        Loop::Continue
+    }
+}
+
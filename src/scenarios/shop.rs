@@ -0,0 +1,284 @@
+extern crate sdl2;
+
+use std::path::Path;
+use std::collections::HashMap;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::pixels::Color;
+use self::sdl2::event::Event;
+use self::sdl2::keyboard::Keycode;
+use self::sdl2::ttf;
+use self::sdl2::render::Texture;
+use self::sdl2::mixer::channel;
+
+use helpers;
+use profiles;
+use characters;
+use save::Save;
+use shop::{self, Item, Ownership};
+use engine::{Scene, Loop, Context};
+
+const ROWS_Y: i32 = -175;
+const ROW_SPACING: i32 = 30;
+const CONFIRM_Y: i32 = 165;
+const BACK_Y: i32 = 245;
+/// Buttons asking to confirm a purchase, by texture name and horizontal
+/// offset.
+const CONFIRM: [(&str, i32); 2] = [("buy", -110), ("cancel", 110)];
+
+/// Every item for sale with its price, bought after a confirmation with the
+/// coins of the profile. Owned skins are worn by clicking them.
+pub struct Shop {
+    textures: HashMap<String, Texture>,
+    items: Vec<Item>,
+    /// Save of the current profile, as written by the last purchase.
+    profile: Save,
+    /// Item clicked and waiting for the purchase to be confirmed.
+    pending: Option<usize>,
+    /// Outcome of the last purchase, and whether it failed.
+    status: Option<(String, bool)>,
+    /// Confirmation button under the mouse.
+    over: Option<usize>,
+    over_back: bool,
+}
+
+impl Shop {
+    fn row(index: usize) -> Rect {
+        helpers::rect_centered(520, 26, 0, ROWS_Y + ROW_SPACING * index as i32)
+    }
+
+    fn confirm_button(index: usize) -> Rect {
+        helpers::rect_centered(200, 50, CONFIRM[index].1, CONFIRM_Y)
+    }
+
+    fn click_row(&mut self, index: usize, ctx: &mut Context) {
+        self.status = None;
+
+        match self.items[index].ownership(&self.profile) {
+            Ownership::Equipped => self.pending = None,
+            Ownership::Owned => {
+                self.pending = None;
+
+                let item = &self.items[index];
+                let path = profiles::path(&profiles::current(&ctx.registry));
+
+                match Save::transact(&path, |save| {
+                    item.equip(save);
+                    Ok(())
+                }) {
+                    Ok(save) => self.profile = save,
+                    Err(err) => self.status = Some((err, true)),
+                }
+            }
+            _ => self.pending = Some(index),
+        }
+    }
+
+    /// Buys the pending item, the coins and the goods written together so
+    /// nothing is paid twice or for nothing.
+    fn buy(&mut self, ctx: &mut Context) {
+        let index = match self.pending.take() {
+            Some(index) => index,
+            None => return,
+        };
+
+        let item = &self.items[index];
+        let path = profiles::path(&profiles::current(&ctx.registry));
+
+        match Save::transact(&path, |save| item.buy(save)) {
+            Ok(save) => {
+                self.profile = save;
+                self.status = Some((format!("Bought {} for {} coins", item.label(), item.price),
+                                    false));
+            }
+            Err(err) => self.status = Some((err, true)),
+        }
+    }
+
+    /// What a row shows right of its label.
+    fn state(&self, item: &Item) -> String {
+        match item.ownership(&self.profile) {
+            Ownership::Buyable => format!("{} coins", item.price),
+            Ownership::Stocked(0) => format!("{} coins", item.price),
+            Ownership::Stocked(count) => format!("x{}  {} coins", count, item.price),
+            Ownership::Owned => "OWNED".into(),
+            Ownership::Equipped => "EQUIPPED".into(),
+        }
+    }
+}
+
+impl Scene for Shop {
+    fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+            items: Vec::new(),
+            profile: Save::new(),
+            pending: None,
+            status: None,
+            over: None,
+            over_back: false,
+        }
+    }
+
+    fn on_event(&mut self, event: Event, ctx: &mut Context) -> Loop {
+        match event {
+            Event::Quit { .. } => Loop::Break,
+            Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                match self.pending.take() {
+                    Some(_) => Loop::Continue,
+                    None => Loop::GoToScene("menu".into()),
+                }
+            }
+            Event::KeyDown { keycode: Some(Keycode::Return), .. } => {
+                self.buy(ctx);
+                Loop::Continue
+            }
+            Event::MouseMotion { x, y, .. } => {
+                let over = match self.pending {
+                    Some(_) => {
+                        (0..CONFIRM.len())
+                            .find(|index| helpers::point_colliding_rect(x, y, &Shop::confirm_button(*index)))
+                    }
+                    None => None,
+                };
+                let over_back =
+                    helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 50, 0, BACK_Y));
+
+                if (over.is_some() && over != self.over) || (over_back && !self.over_back) {
+                    channel(1)
+                        .play(ctx.sounds.get("menu").unwrap(), 0)
+                        .unwrap();
+                }
+
+                self.over = over;
+                self.over_back = over_back;
+                Loop::Continue
+            }
+            Event::MouseButtonUp { x, y, .. } => {
+                if helpers::point_colliding_rect(x, y, &helpers::rect_centered(200, 50, 0, BACK_Y)) {
+                    return Loop::GoToScene("menu".into());
+                }
+
+                if self.pending.is_some() {
+                    match (0..CONFIRM.len())
+                              .find(|index| helpers::point_colliding_rect(x, y, &Shop::confirm_button(*index))) {
+                        Some(0) => self.buy(ctx),
+                        Some(_) => self.pending = None,
+                        None => {}
+                    }
+                }
+
+                if let Some(index) = (0..self.items.len())
+                       .find(|index| helpers::point_colliding_rect(x, y, &Shop::row(*index))) {
+                    self.click_row(index, ctx);
+                }
+
+                Loop::Continue
+            }
+            _ => Loop::Continue,
+        }
+    }
+
+    fn on_load(&mut self, ctx: &mut Context) -> Loop {
+        let mut font = ctx.ttf_context
+            .load_font(Path::new("./assets/font.ttf"), 128)
+            .unwrap();
+
+        font.set_style(ttf::STYLE_BOLD);
+
+        for &(name, text) in &[("title", "Shop"), ("buy", "BUY"), ("cancel", "CANCEL"), ("back", "BACK")] {
+            let surface = font.render(text)
+                .blended(Color::RGBA(0, 0, 0, 255))
+                .unwrap();
+
+            self.textures
+                .insert(name.into(),
+                        ctx.renderer
+                            .create_texture_from_surface(&surface)
+                            .unwrap());
+        }
+
+        let characters = characters::load(Path::new("./assets/game.characters")).unwrap();
+
+        self.items = shop::load(Path::new("./assets/game.shop"), &characters).unwrap();
+        self.pending = None;
+        self.status = None;
+        self.profile = profiles::load(&ctx.registry).unwrap_or_else(|err| {
+            self.status = Some((err, true));
+            Save::new()
+        });
+
+        Loop::Continue
+    }
+
+    fn on_tick(&mut self, ctx: &mut Context) -> Loop {
+        ctx.renderer.set_draw_color(Color::RGB(255, 255, 255));
+        ctx.renderer.clear();
+
+        // Renders the title
+        {
+            let title = self.textures.get("title").unwrap();
+
+            ctx.renderer
+                .copy(title, None, Some(helpers::rect_centered(160, 40, 0, -250)))
+                .unwrap();
+        }
+
+        helpers::draw_text(&format!("Coins: {}", self.profile.coins),
+                           Color::RGBA(0, 0, 0, 255),
+                           (0, -210),
+                           ctx);
+
+        // Renders every item, the one being bought framed in red
+        for (index, item) in self.items.iter().enumerate() {
+            let y = ROWS_Y + ROW_SPACING * index as i32;
+
+            if self.pending == Some(index) {
+                ctx.renderer.set_draw_color(Color::RGB(255, 0, 0));
+                ctx.renderer.draw_rect(Shop::row(index)).unwrap();
+            }
+
+            let color = match item.ownership(&self.profile) {
+                Ownership::Owned | Ownership::Equipped => Color::RGBA(0, 140, 0, 255),
+                _ if self.profile.coins < item.price => Color::RGBA(150, 150, 150, 255),
+                _ => Color::RGBA(0, 0, 0, 255),
+            };
+
+            helpers::draw_text(&item.label(), color, (-120, y), ctx);
+            helpers::draw_text(&self.state(item), color, (170, y), ctx);
+        }
+
+        if let Some(index) = self.pending {
+            let item = &self.items[index];
+
+            helpers::draw_text(&format!("Buy {} for {} coins?", item.label(), item.price),
+                               Color::RGBA(0, 0, 0, 255),
+                               (0, CONFIRM_Y - 45),
+                               ctx);
+
+            for (button, &(name, _)) in CONFIRM.iter().enumerate() {
+                helpers::draw_button(Shop::confirm_button(button),
+                                     self.textures.get(name).unwrap(),
+                                     (16 * name.len() as u32, 20),
+                                     self.over == Some(button),
+                                     ctx);
+            }
+        } else if let Some((ref message, failed)) = self.status {
+            let color = if failed {
+                Color::RGBA(200, 0, 0, 255)
+            } else {
+                Color::RGBA(0, 140, 0, 255)
+            };
+
+            helpers::draw_text(message, color, (0, CONFIRM_Y - 45), ctx);
+        }
+
+        helpers::draw_button(helpers::rect_centered(200, 50, 0, BACK_Y),
+                             self.textures.get("back").unwrap(),
+                             (64, 20),
+                             self.over_back,
+                             ctx);
+
+        Loop::Continue
+    }
+}
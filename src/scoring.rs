@@ -0,0 +1,247 @@
+use std::path::Path;
+
+use data;
+
+/// Where the points of a run came from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Category {
+    Distance,
+    Obstacles,
+    NearMisses,
+    /// Points added by the combo multiplier on top of obstacles.
+    Combos,
+}
+
+impl Category {
+    pub const ALL: [Category; 4] =
+        [Category::Distance, Category::Obstacles, Category::NearMisses, Category::Combos];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Category::Distance => "distance",
+            Category::Obstacles => "obstacles",
+            Category::NearMisses => "near_misses",
+            Category::Combos => "combos",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Category::Distance => "Distance",
+            Category::Obstacles => "Obstacles",
+            Category::NearMisses => "Near misses",
+            Category::Combos => "Combos",
+        }
+    }
+}
+
+/// How points are given, loaded from a `.scoring` file:
+///
+/// ```text
+/// distance   every=100 points=1
+/// obstacle   base=10 per_size=5
+/// near_miss  margin=24 bonus=25
+/// combo      every=5 max=5
+/// ```
+pub struct Rules {
+    /// Pixels travelled for `distance_points`.
+    pub distance_every: f32,
+    pub distance_points: u32,
+    pub obstacle_base: u32,
+    /// Extra points for every rock of a stack past the first.
+    pub obstacle_per_size: u32,
+    /// Clearance in pixels under which clearing an obstacle is a near miss.
+    pub near_miss_margin: f32,
+    pub near_miss_bonus: u32,
+    /// Consecutive clears that raise the combo multiplier by one.
+    pub combo_every: u32,
+    pub combo_max: u32,
+}
+
+impl Rules {
+    pub fn new() -> Self {
+        Self {
+            distance_every: 100.0,
+            distance_points: 1,
+            obstacle_base: 10,
+            obstacle_per_size: 5,
+            near_miss_margin: 24.0,
+            near_miss_bonus: 25,
+            combo_every: 5,
+            combo_max: 5,
+        }
+    }
+
+    /// Values left out of the file keep the ones of `Rules::new`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut rules = Rules::new();
+
+        for entry in data::load(path)? {
+            match entry.keyword.as_str() {
+                "distance" => {
+                    rules.distance_every = entry.option_or("every", rules.distance_every)?;
+                    rules.distance_points = entry.option_or("points", rules.distance_points)?;
+                }
+                "obstacle" => {
+                    rules.obstacle_base = entry.option_or("base", rules.obstacle_base)?;
+                    rules.obstacle_per_size = entry.option_or("per_size", rules.obstacle_per_size)?;
+                }
+                "near_miss" => {
+                    rules.near_miss_margin = entry.option_or("margin", rules.near_miss_margin)?;
+                    rules.near_miss_bonus = entry.option_or("bonus", rules.near_miss_bonus)?;
+                }
+                "combo" => {
+                    rules.combo_every = entry.option_or("every", rules.combo_every)?.max(1);
+                    rules.combo_max = entry.option_or("max", rules.combo_max)?.max(1);
+                }
+                other => return Err(format!("line {}: unknown keyword `{}`", entry.line, other)),
+            }
+        }
+
+        Ok(rules)
+    }
+}
+
+/// Score of a run, kept per category.
+pub struct Scoring {
+    pub rules: Rules,
+    totals: [u32; 4],
+    /// Obstacles cleared since the last hit.
+    pub combo: u32,
+    /// Distance travelled that didn't give a point yet.
+    travelled: f32,
+}
+
+impl Scoring {
+    pub fn new(rules: Rules) -> Self {
+        Self {
+            rules,
+            totals: [0; 4],
+            combo: 0,
+            travelled: 0.0,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.totals.iter().sum()
+    }
+
+    pub fn category(&self, category: Category) -> u32 {
+        self.totals[category as usize]
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.combo / self.rules.combo_every).min(self.rules.combo_max)
+    }
+
+    fn add(&mut self, category: Category, points: u32) {
+        self.totals[category as usize] += points;
+    }
+
+    pub fn travel(&mut self, distance: f32) {
+        self.travelled += distance.max(0.0);
+
+        while self.travelled >= self.rules.distance_every {
+            self.travelled -= self.rules.distance_every;
+            self.add(Category::Distance, self.rules.distance_points);
+        }
+    }
+
+    /// Scores an obstacle of `size` left behind, `clearance` pixels away
+    /// from the player at its closest, all of it multiplied by `bonus`.
+    /// Returns what was given, for popups.
+    pub fn clear(&mut self, size: u32, clearance: f32, bonus: u32) -> Vec<(Category, u32)> {
+        let mut awards = Vec::new();
+        let extra = self.rules.obstacle_per_size * size.max(1).saturating_sub(1);
+        let base = (self.rules.obstacle_base + extra) * bonus;
+
+        self.combo += 1;
+        awards.push((Category::Obstacles, base));
+
+        if self.multiplier() > 1 {
+            awards.push((Category::Combos, base * (self.multiplier() - 1)));
+        }
+
+        if clearance >= 0.0 && clearance < self.rules.near_miss_margin {
+            awards.push((Category::NearMisses, self.rules.near_miss_bonus * bonus));
+        }
+
+        for &(category, points) in &awards {
+            self.add(category, points);
+        }
+
+        awards
+    }
+
+    /// A hit ends the combo.
+    pub fn break_combo(&mut self) {
+        self.combo = 0;
+    }
+}
+
+/// Floating text rising from where points were given.
+pub struct Popup {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    /// Ticks since it appeared.
+    pub age: f32,
+}
+
+impl Popup {
+    /// Ticks a popup stays on screen.
+    pub const LIFETIME: f32 = 50.0;
+
+    pub fn new(text: String, x: f32, y: f32) -> Self {
+        Self { text, x, y, age: 0.0 }
+    }
+
+    pub fn alpha(&self) -> u8 {
+        (255.0 * (1.0 - self.age / Popup::LIFETIME).max(0.0)) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Category, Rules, Scoring};
+
+    #[test]
+    fn scores_the_size_of_a_stack() {
+        let mut scoring = Scoring::new(Rules::new());
+
+        assert_eq!(scoring.clear(3, 100.0, 1), vec![(Category::Obstacles, 20)]);
+        assert_eq!(scoring.total(), 20);
+    }
+
+    #[test]
+    fn raises_the_multiplier_every_few_clears_up_to_its_max() {
+        let mut scoring = Scoring::new(Rules::new());
+
+        for _ in 0..4 {
+            scoring.clear(1, 100.0, 1);
+        }
+
+        assert_eq!(scoring.multiplier(), 1);
+        assert_eq!(scoring.clear(1, 100.0, 1),
+                   vec![(Category::Obstacles, 10), (Category::Combos, 10)]);
+
+        for _ in 0..50 {
+            scoring.clear(1, 100.0, 1);
+        }
+
+        assert_eq!(scoring.multiplier(), 5);
+
+        scoring.break_combo();
+
+        assert_eq!(scoring.multiplier(), 1);
+    }
+
+    #[test]
+    fn gives_near_misses_and_bonuses() {
+        let mut scoring = Scoring::new(Rules::new());
+
+        assert_eq!(scoring.clear(1, 10.0, 2),
+                   vec![(Category::Obstacles, 20), (Category::NearMisses, 50)]);
+        assert_eq!(scoring.category(Category::NearMisses), 50);
+    }
+}
@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use data;
+use save::Save;
+use powerups::Power;
+use characters::{self, Character};
+
+/// Name continues are stocked under in the save.
+pub const CONTINUE: &str = "continue";
+
+/// What an item of the shop gives.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Goods {
+    /// A skin of a character, by their names.
+    Skin(String, String),
+    /// A power granted at the start of a run, one used per run.
+    Power(Power),
+    /// Gets back up once when a run would end.
+    Continue,
+}
+
+/// Where the profile stands with an item.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ownership {
+    Buyable,
+    /// Consumables bought and not used yet.
+    Stocked(u64),
+    Owned,
+    /// An owned skin being worn.
+    Equipped,
+}
+
+pub struct Item {
+    pub goods: Goods,
+    pub price: u64,
+}
+
+impl Item {
+    pub fn label(&self) -> String {
+        match self.goods {
+            Goods::Skin(ref character, ref skin) => format!("{} {} skin", character, skin),
+            Goods::Power(power) => format!("{} at start", power.name()),
+            Goods::Continue => "Continue".into(),
+        }
+    }
+
+    pub fn ownership(&self, save: &Save) -> Ownership {
+        match self.goods {
+            Goods::Skin(ref character, ref skin) => {
+                let equipped = save.setting("character") == Some(character.as_str()) &&
+                               save.setting("skin") == Some(skin.as_str());
+
+                if equipped {
+                    Ownership::Equipped
+                } else if save.owns(character, skin) {
+                    Ownership::Owned
+                } else {
+                    Ownership::Buyable
+                }
+            }
+            Goods::Power(power) => Ownership::Stocked(save.stock(power.name())),
+            Goods::Continue => Ownership::Stocked(save.stock(CONTINUE)),
+        }
+    }
+
+    /// Takes the price from the wallet and gives the goods, or changes
+    /// nothing when they can't be bought.
+    pub fn buy(&self, save: &mut Save) -> Result<(), String> {
+        if let Ownership::Owned | Ownership::Equipped = self.ownership(save) {
+            return Err(format!("{} is already owned", self.label()));
+        }
+
+        if save.coins < self.price {
+            return Err(format!("{} needs {} more coins", self.label(), self.price - save.coins));
+        }
+
+        save.coins -= self.price;
+
+        match self.goods {
+            Goods::Skin(ref character, ref skin) => {
+                save.owned.push((character.clone(), skin.clone()));
+            }
+            Goods::Power(power) => *save.stock.entry(power.name().into()).or_insert(0) += 1,
+            Goods::Continue => *save.stock.entry(CONTINUE.into()).or_insert(0) += 1,
+        }
+
+        Ok(())
+    }
+
+    /// Wears a skin that is owned.
+    pub fn equip(&self, save: &mut Save) {
+        if let Goods::Skin(ref character, ref skin) = self.goods {
+            save.settings.insert("character".into(), character.clone());
+            save.settings.insert("skin".into(), skin.clone());
+        }
+    }
+}
+
+/// The skins of every character that cost something, then the items of a
+/// `.shop` file:
+///
+/// ```text
+/// power    shield price=120
+/// continue price=250
+/// ```
+pub fn load(path: &Path, characters: &[Character]) -> Result<Vec<Item>, String> {
+    let mut items = Vec::new();
+
+    for character in characters {
+        for skin in &character.skins {
+            if skin.price > 0 {
+                items.push(Item {
+                               goods: Goods::Skin(character.name.clone(), skin.name.clone()),
+                               price: skin.price,
+                           });
+            }
+        }
+    }
+
+    for entry in data::load(path)? {
+        let goods = match entry.keyword.as_str() {
+            "power" => {
+                let name: String = entry.arg(0)?;
+
+                match Power::parse(&name) {
+                    Some(power) => Goods::Power(power),
+                    None => return Err(format!("line {}: unknown power `{}`", entry.line, name)),
+                }
+            }
+            "continue" => Goods::Continue,
+            other => return Err(format!("line {}: unknown keyword `{}`", entry.line, other)),
+        };
+
+        if entry.option("price").is_none() {
+            return Err(format!("line {}: `{}` needs a price", entry.line, entry.keyword));
+        }
+
+        items.push(Item {
+                       goods,
+                       price: entry.option_or("price", 0)?,
+                   });
+    }
+
+    Ok(items)
+}
+
+/// The shop item selling a skin, for buying it from elsewhere.
+pub fn skin_item(character: &Character, skin: &characters::Skin) -> Item {
+    Item {
+        goods: Goods::Skin(character.name.clone(), skin.name.clone()),
+        price: skin.price,
+    }
+}
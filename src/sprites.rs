@@ -0,0 +1,212 @@
+extern crate sdl2;
+
+use std::path::Path;
+use std::collections::HashMap;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::render::{Renderer, Texture};
+
+use data;
+use camera::Camera;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Playback {
+    Loop,
+    Once,
+}
+
+#[derive(Clone)]
+pub struct Frame {
+    pub rect: Rect,
+    pub duration: u64,
+}
+
+#[derive(Clone)]
+pub struct Animation {
+    pub frames: Vec<Frame>,
+    pub playback: Playback,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+impl Animation {
+    pub fn length(&self) -> u64 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// Frame layout of a texture, loaded from a `.sheet` file next to the image.
+///
+/// ```text
+/// texture ./assets/player.png
+/// grid 46 50
+/// animation run loop 100 0,3 1,3 2,3 flip_x
+/// ```
+///
+/// Frames are either grid cells (`column,row`) or explicit rects
+/// (`x,y,width,height`), optionally followed by `:millis` to override the
+/// animation's frame duration.
+pub struct SpriteSheet {
+    pub texture: String,
+    pub animations: HashMap<String, Animation>,
+}
+
+impl SpriteSheet {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut texture = String::new();
+        let mut grid = (0u32, 0u32);
+        let mut animations = HashMap::new();
+
+        for entry in data::load(path)? {
+            match entry.keyword.as_str() {
+                "texture" => texture = entry.arg(0)?,
+                "grid" => grid = (entry.arg(0)?, entry.arg(1)?),
+                "animation" => {
+                    let name: String = entry.arg(0)?;
+                    let playback = match entry.arg::<String>(1)?.as_str() {
+                        "loop" => Playback::Loop,
+                        "once" => Playback::Once,
+                        other => {
+                            return Err(format!("line {}: unknown playback `{}`", entry.line, other))
+                        }
+                    };
+                    let duration: u64 = entry.arg(2)?;
+                    let mut frames = Vec::new();
+
+                    for token in entry.positional().iter().skip(3) {
+                        if token.starts_with("flip_") {
+                            continue;
+                        }
+
+                        frames.push(parse_frame(token, grid, duration)
+                                        .map_err(|err| format!("line {}: {}", entry.line, err))?);
+                    }
+
+                    if frames.is_empty() {
+                        return Err(format!("line {}: animation `{}` has no frames",
+                                           entry.line,
+                                           name));
+                    }
+
+                    animations.insert(name,
+                                      Animation {
+                                          frames,
+                                          playback,
+                                          flip_horizontal: entry.has_flag("flip_x"),
+                                          flip_vertical: entry.has_flag("flip_y"),
+                                      });
+                }
+                other => return Err(format!("line {}: unknown keyword `{}`", entry.line, other)),
+            }
+        }
+
+        Ok(Self {
+               texture,
+               animations,
+           })
+    }
+
+    pub fn animation(&self, name: &str) -> &Animation {
+        self.animations
+            .get(name)
+            .unwrap_or_else(|| panic!("missing animation `{}` in {}", name, self.texture))
+    }
+
+    pub fn draw(&self,
+                renderer: &mut Renderer,
+                camera: &Camera,
+                texture: &Texture,
+                animator: &Animator,
+                (x, y): (i32, i32),
+                scale: i32) {
+        let animation = self.animation(&animator.animation);
+        let frame = animator.frame(animation);
+        let destination = Rect::new(x,
+                                    y,
+                                    frame.rect.width() * scale as u32,
+                                    frame.rect.height() * scale as u32);
+
+        camera.copy_ex(renderer,
+                       texture,
+                       Some(frame.rect),
+                       destination,
+                       animation.flip_horizontal,
+                       animation.flip_vertical);
+    }
+}
+
+fn parse_frame(token: &str, grid: (u32, u32), default_duration: u64) -> Result<Frame, String> {
+    let mut parts = token.splitn(2, ':');
+    let cell = parts.next().unwrap_or("");
+    let duration = match parts.next() {
+        Some(millis) => millis
+            .parse::<u64>()
+            .map_err(|_| format!("invalid frame duration `{}`", millis))?,
+        None => default_duration,
+    };
+
+    let numbers = cell.split(',')
+        .map(|number| number.parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| format!("invalid frame `{}`", token))?;
+
+    let rect = match numbers.len() {
+        2 => {
+            Rect::new(numbers[0] * grid.0 as i32,
+                      numbers[1] * grid.1 as i32,
+                      grid.0,
+                      grid.1)
+        }
+        4 => Rect::new(numbers[0], numbers[1], numbers[2] as u32, numbers[3] as u32),
+        _ => return Err(format!("invalid frame `{}`", token)),
+    };
+
+    Ok(Frame { rect, duration })
+}
+
+/// Playback state of one animation, advanced by simulation time rather than
+/// the wall clock so it freezes together with the world.
+#[derive(Clone)]
+pub struct Animator {
+    pub animation: String,
+    pub elapsed: u64,
+}
+
+impl Animator {
+    pub fn new(animation: &str) -> Self {
+        Self {
+            animation: animation.into(),
+            elapsed: 0,
+        }
+    }
+
+    /// Switches to another animation, restarting it only when it changes.
+    pub fn play(&mut self, animation: &str) {
+        if self.animation != animation {
+            self.animation = animation.into();
+            self.elapsed = 0;
+        }
+    }
+
+    pub fn advance(&mut self, millis: u64) {
+        self.elapsed += millis;
+    }
+
+    pub fn frame<'a>(&self, animation: &'a Animation) -> &'a Frame {
+        let length = animation.length();
+        let mut position = match animation.playback {
+            Playback::Loop if length > 0 => self.elapsed % length,
+            _ => self.elapsed,
+        };
+
+        for frame in &animation.frames {
+            if position < frame.duration {
+                return frame;
+            }
+
+            position -= frame.duration;
+        }
+
+        animation.frames.last().unwrap()
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use config;
+use engine::RegistryItem;
+
+/// A number tracked over a run, with a personal best kept in the save.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stat {
+    Score,
+    /// In ticks.
+    Duration,
+    /// In pixels.
+    Distance,
+    Jumps,
+    Cleared,
+    MaxCombo,
+    Coins,
+    NearMisses,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 8] = [Stat::Score,
+                                Stat::Duration,
+                                Stat::Distance,
+                                Stat::Jumps,
+                                Stat::Cleared,
+                                Stat::MaxCombo,
+                                Stat::Coins,
+                                Stat::NearMisses];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Stat::Score => "score",
+            Stat::Duration => "duration",
+            Stat::Distance => "distance",
+            Stat::Jumps => "jumps",
+            Stat::Cleared => "cleared",
+            Stat::MaxCombo => "max_combo",
+            Stat::Coins => "coins",
+            Stat::NearMisses => "near_misses",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Stat::ALL.iter().cloned().find(|stat| stat.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Stat::Score => "Score",
+            Stat::Duration => "Time",
+            Stat::Distance => "Distance",
+            Stat::Jumps => "Jumps",
+            Stat::Cleared => "Obstacles",
+            Stat::MaxCombo => "Best combo",
+            Stat::Coins => "Coins",
+            Stat::NearMisses => "Near misses",
+        }
+    }
+
+    /// The value as shown to the player.
+    pub fn format(&self, value: u64) -> String {
+        match *self {
+            Stat::Duration => {
+                let seconds = value * config::TICK_MILLIS / 1000;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+            // A meter is about a third of the player's height
+            Stat::Distance => format!("{} m", value / 50),
+            _ => value.to_string(),
+        }
+    }
+}
+
+/// Everything tracked during a single run.
+pub struct RunStats {
+    values: [u64; 8],
+    /// Obstacles cleared, indexed by their size minus one.
+    pub cleared_by_size: Vec<u32>,
+    /// What ended the run.
+    pub cause: String,
+}
+
+impl RunStats {
+    pub fn new() -> Self {
+        Self {
+            values: [0; 8],
+            cleared_by_size: Vec::new(),
+            cause: String::new(),
+        }
+    }
+
+    pub fn get(&self, stat: Stat) -> u64 {
+        self.values[stat as usize]
+    }
+
+    pub fn set(&mut self, stat: Stat, value: u64) {
+        self.values[stat as usize] = value;
+    }
+
+    pub fn add(&mut self, stat: Stat, value: u64) {
+        self.values[stat as usize] += value;
+    }
+
+    pub fn clear_obstacle(&mut self, size: u32) {
+        let index = size.max(1) as usize - 1;
+
+        if self.cleared_by_size.len() <= index {
+            self.cleared_by_size.resize(index + 1, 0);
+        }
+
+        self.cleared_by_size[index] += 1;
+        self.add(Stat::Cleared, 1);
+    }
+
+    /// Size of the biggest stack cleared, zero before the first.
+    pub fn largest_stack(&self) -> u64 {
+        self.cleared_by_size.len() as u64
+    }
+
+    /// Stores every stat in the registry, under `stat_` followed by its
+    /// name, for the game over screen.
+    pub fn publish(&self, registry: &mut HashMap<String, RegistryItem>) {
+        for stat in &Stat::ALL {
+            registry.insert(format!("stat_{}", stat.name()),
+                            RegistryItem::Number(self.get(*stat) as usize));
+        }
+
+        let by_size = self.cleared_by_size
+            .iter()
+            .enumerate()
+            .filter(|&(_, count)| *count > 0)
+            .map(|(index, count)| format!("{}x size {}", count, index + 1))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        registry.insert("stat_cleared_by_size".into(), RegistryItem::Text(by_size));
+        registry.insert("stat_cause".into(), RegistryItem::Text(self.cause.clone()));
+    }
+}
+
+/// A number stored in the registry, zero when missing.
+pub fn number(registry: &HashMap<String, RegistryItem>, key: &str) -> u64 {
+    match registry.get(key) {
+        Some(RegistryItem::Number(value)) => *value as u64,
+        _ => 0,
+    }
+}
+
+/// Text stored in the registry, empty when missing.
+pub fn text(registry: &HashMap<String, RegistryItem>, key: &str) -> String {
+    match registry.get(key) {
+        Some(RegistryItem::Text(value)) => value.clone(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,184 @@
+extern crate sdl2;
+
+use self::sdl2::rect::Rect;
+use self::sdl2::pixels::{Color, PixelFormatEnum};
+use self::sdl2::render::{Renderer, Texture, BlendMode};
+
+/// Visual effect played by the engine while switching scenes. Durations are
+/// in milliseconds of simulation time.
+#[derive(Clone, Copy)]
+pub enum Transition {
+    /// Fades the outgoing scene into a colour, then the colour into the
+    /// incoming scene.
+    Fade(Color, u64),
+    Crossfade(u64),
+    Slide(Direction, u64),
+    /// Reveals the incoming scene through a circle growing from the center.
+    CircleWipe(u64),
+}
+
+#[derive(Clone, Copy)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Transition {
+    /// Parses the names accepted by the console's `scene` command.
+    pub fn parse(name: &str) -> Result<Transition, String> {
+        match name {
+            "fade" => Ok(Transition::Fade(Color::RGB(0, 0, 0), 600)),
+            "crossfade" => Ok(Transition::Crossfade(600)),
+            "wipe" => Ok(Transition::CircleWipe(700)),
+            "slide_left" => Ok(Transition::Slide(Direction::Left, 500)),
+            "slide_right" => Ok(Transition::Slide(Direction::Right, 500)),
+            "slide_up" => Ok(Transition::Slide(Direction::Up, 500)),
+            "slide_down" => Ok(Transition::Slide(Direction::Down, 500)),
+            other => Err(format!("unknown transition `{}`", other)),
+        }
+    }
+
+    fn duration(&self) -> u64 {
+        match *self {
+            Transition::Fade(_, duration) |
+            Transition::Crossfade(duration) |
+            Transition::Slide(_, duration) |
+            Transition::CircleWipe(duration) => duration,
+        }
+    }
+}
+
+/// Copies what has been drawn so far into a texture, so a scene's last frame
+/// can outlive the scene. Must be called before `present()`.
+pub fn capture(renderer: &Renderer) -> Texture {
+    let (width, height) = renderer.output_size().unwrap();
+    let pixels = renderer
+        .read_pixels(None, PixelFormatEnum::ARGB8888)
+        .unwrap();
+    let mut texture = renderer
+        .create_texture_static(PixelFormatEnum::ARGB8888, width, height)
+        .unwrap();
+
+    texture
+        .update(None, &pixels, width as usize * 4)
+        .unwrap();
+    texture.set_blend_mode(BlendMode::Blend);
+
+    texture
+}
+
+pub struct ActiveTransition {
+    pub transition: Transition,
+    pub from: Texture,
+    pub to: Texture,
+    pub elapsed: u64,
+}
+
+impl ActiveTransition {
+    pub fn new(transition: Transition, from: Texture, to: Texture) -> Self {
+        Self {
+            transition,
+            from,
+            to,
+            elapsed: 0,
+        }
+    }
+
+    pub fn advance(&mut self, millis: u64) {
+        self.elapsed += millis;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.transition.duration()
+    }
+
+    fn progress(&self) -> f32 {
+        let duration = self.transition.duration().max(1);
+        (self.elapsed.min(duration) as f32) / duration as f32
+    }
+
+    pub fn draw(&mut self, renderer: &mut Renderer) {
+        let progress = self.progress();
+        let (width, height) = renderer.output_size().unwrap();
+
+        renderer.set_draw_color(Color::RGB(0, 0, 0));
+        renderer.clear();
+
+        match self.transition {
+            Transition::Fade(color, _) => {
+                let (frame, alpha) = if progress < 0.5 {
+                    (&self.from, progress * 2.0)
+                } else {
+                    (&self.to, (1.0 - progress) * 2.0)
+                };
+                let (red, green, blue) = match color {
+                    Color::RGB(red, green, blue) |
+                    Color::RGBA(red, green, blue, _) => (red, green, blue),
+                };
+
+                renderer.copy(frame, None, None).unwrap();
+                renderer.set_blend_mode(BlendMode::Blend);
+                renderer.set_draw_color(Color::RGBA(red, green, blue, (alpha * 255.0) as u8));
+                renderer.fill_rect(None).unwrap();
+                renderer.set_blend_mode(BlendMode::None);
+            }
+            Transition::Crossfade(_) => {
+                renderer.copy(&self.from, None, None).unwrap();
+                self.to.set_alpha_mod((progress * 255.0) as u8);
+                renderer.copy(&self.to, None, None).unwrap();
+                self.to.set_alpha_mod(255);
+            }
+            Transition::Slide(direction, _) => {
+                let (width, height) = (width as i32, height as i32);
+                let (offset_x, offset_y) = match direction {
+                    Direction::Left => (-(width as f32 * progress) as i32, 0),
+                    Direction::Right => ((width as f32 * progress) as i32, 0),
+                    Direction::Up => (0, -(height as f32 * progress) as i32),
+                    Direction::Down => (0, (height as f32 * progress) as i32),
+                };
+                let (incoming_x, incoming_y) = match direction {
+                    Direction::Left => (offset_x + width, 0),
+                    Direction::Right => (offset_x - width, 0),
+                    Direction::Up => (0, offset_y + height),
+                    Direction::Down => (0, offset_y - height),
+                };
+
+                renderer
+                    .copy(&self.from,
+                          None,
+                          Some(Rect::new(offset_x, offset_y, width as u32, height as u32)))
+                    .unwrap();
+                renderer
+                    .copy(&self.to,
+                          None,
+                          Some(Rect::new(incoming_x, incoming_y, width as u32, height as u32)))
+                    .unwrap();
+            }
+            Transition::CircleWipe(_) => {
+                let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+                let radius = (center_x * center_x + center_y * center_y).sqrt() * progress;
+
+                renderer.copy(&self.from, None, None).unwrap();
+
+                // SDL can't clip to a circle, so the incoming frame is copied
+                // one horizontal span per row.
+                let top = (center_y - radius).max(0.0) as i32;
+                let bottom = (center_y + radius).min(height as f32) as i32;
+
+                for y in top..bottom {
+                    let distance = y as f32 + 0.5 - center_y;
+                    let half = (radius * radius - distance * distance).max(0.0).sqrt();
+                    let left = (center_x - half).max(0.0) as i32;
+                    let right = (center_x + half).min(width as f32) as i32;
+
+                    if right > left {
+                        let span = Rect::new(left, y, (right - left) as u32, 1);
+                        renderer.copy(&self.to, Some(span), Some(span)).unwrap();
+                    }
+                }
+            }
+        }
+    }
+}